@@ -0,0 +1,29 @@
+use std::{env, process::Command, time::{SystemTime, UNIX_EPOCH}};
+
+/// what `--version --verbose` prints, so a bug report says which build it came from.
+fn main() {
+    let git = |args: &[&str]| Command::new("git").args(args).output().ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let commit = match git(&["rev-parse", "--short=12", "HEAD"]) {
+        Some(commit) if git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|status| !status.is_empty()) => format!("{}-dirty", commit),
+        Some(commit) => commit,
+        None => "unknown".to_string(),
+    };
+    // SOURCE_DATE_EPOCH keeps reproducible builds reproducible.
+    let built = env::var("SOURCE_DATE_EPOCH").ok().and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(|feature| feature.to_ascii_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=POST_REMOVE_COMMIT={}", commit);
+    println!("cargo:rustc-env=POST_REMOVE_BUILT={}", built);
+    println!("cargo:rustc-env=POST_REMOVE_TARGET={}", env::var("TARGET").unwrap_or_default());
+    println!("cargo:rustc-env=POST_REMOVE_PROFILE={}", env::var("PROFILE").unwrap_or_default());
+    println!("cargo:rustc-env=POST_REMOVE_FEATURES={}", features.join(","));
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
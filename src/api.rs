@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Response};
+use serde_json::Value;
+use std::sync::Mutex;
+
+#[cfg(feature = "x-v2")]
+use crate::oauth2;
+use crate::{cli::ApiVersion, clock::SharedClock, delete_tweet, record, replay::Replay, Credentials};
+
+/// requests per window of the simulated API, like the v1.1 statuses/destroy limit.
+const SIMULATED_LIMIT: u32 = 50;
+const SIMULATED_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// what undoes an entry of the archive.
+#[derive(Clone, Copy)]
+pub enum Action {
+    Delete,
+    /// a plain retweet. `source` is the retweeted post, when the entry says which.
+    Unretweet { source: Option<u64> },
+    /// an entry of like.js. the post is someone else's, only the like goes.
+    Unlike,
+    /// a message of direct-messages.js.
+    DeleteMessage,
+    /// a bookmark listed by the `bookmarks` subcommand.
+    #[cfg(feature = "x-v2")]
+    Unbookmark,
+}
+
+impl Action {
+    /// `entry` is an archive entry. quote posts are posts of their own and get deleted. only plain retweets are undone.
+    pub fn of(entry: &Value) -> Self {
+        if entry["like"].is_object() {
+            return Action::Unlike;
+        }
+        if entry["dm"].is_object() {
+            return Action::DeleteMessage;
+        }
+        #[cfg(feature = "x-v2")]
+        if entry["bookmark"].is_object() {
+            return Action::Unbookmark;
+        }
+        let tweet = &entry["tweet"];
+        let retweeted = &tweet["retweeted_status"];
+        let is_retweet = retweeted.is_object() || crate::text::of(tweet).starts_with("RT @");
+        if !is_retweet {
+            return Action::Delete;
+        }
+        Action::Unretweet { source: retweeted["id_str"].as_str().and_then(|id| id.parse().ok()) }
+    }
+
+    /// how events name it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::Unretweet { .. } => "unretweet",
+            Action::Unlike => "unlike",
+            Action::DeleteMessage => "delete_message",
+            #[cfg(feature = "x-v2")]
+            Action::Unbookmark => "unbookmark",
+        }
+    }
+
+    pub fn done(self) -> &'static str {
+        match self {
+            Action::Delete => "deleted",
+            Action::Unretweet { .. } => "unretweeted",
+            Action::Unlike => "unliked",
+            Action::DeleteMessage => "deleted message",
+            #[cfg(feature = "x-v2")]
+            Action::Unbookmark => "unbookmarked",
+        }
+    }
+}
+
+/// method and url that apply `action` to the post `id` of `user_id`.
+pub fn endpoint(id: u64, action: Action, version: ApiVersion, #[cfg_attr(not(feature = "x-v2"), allow(unused_variables))] user_id: &str) -> (Method, String) {
+    match (version, action) {
+        // there are no bookmarks in v1.1.
+        #[cfg(feature = "x-v2")]
+        (_, Action::Unbookmark) => (Method::DELETE, format!("https://api.x.com/2/users/{}/bookmarks/{}", user_id, id)),
+        #[cfg(feature = "x-v1")]
+        (ApiVersion::V1, Action::Delete) => (Method::POST, format!("https://api.x.com/1.1/statuses/destroy/{}.json", id)),
+        #[cfg(feature = "x-v1")]
+        (ApiVersion::V1, Action::Unretweet { source }) => (Method::POST, format!("https://api.x.com/1.1/statuses/unretweet/{}.json", source.unwrap_or(id))),
+        #[cfg(feature = "x-v1")]
+        (ApiVersion::V1, Action::Unlike) => (Method::POST, format!("https://api.x.com/1.1/favorites/destroy.json?id={}", id)),
+        #[cfg(feature = "x-v1")]
+        (ApiVersion::V1, Action::DeleteMessage) => (Method::DELETE, format!("https://api.x.com/1.1/direct_messages/events/destroy.json?id={}", id)),
+        #[cfg(feature = "x-v2")]
+        (ApiVersion::V2, Action::Unlike) => (Method::DELETE, format!("https://api.x.com/2/users/{}/likes/{}", user_id, id)),
+        #[cfg(feature = "x-v2")]
+        (ApiVersion::V2, Action::DeleteMessage) => (Method::DELETE, format!("https://api.x.com/2/dm_events/{}", id)),
+        #[cfg(feature = "x-v2")]
+        (ApiVersion::V2, Action::Unretweet { source: Some(source) }) => (Method::DELETE, format!("https://api.x.com/2/users/{}/retweets/{}", user_id, source)),
+        // without the source, deleting the retweet itself undoes it too.
+        #[cfg(feature = "x-v2")]
+        (ApiVersion::V2, _) => (Method::DELETE, format!("https://api.x.com/2/tweets/{}", id)),
+    }
+}
+
+/// why `api` (v1.1 or v2) can't be used, with the feature that brings it.
+pub fn not_compiled(api: &str) -> String {
+    let feature = if api == "v1.1" { "x-v1" } else { "x-v2" };
+    format!("the {} API isn't compiled into this build. rebuild with --features {}.", api, feature)
+}
+
+/// where delete requests go.
+pub enum Api {
+    X { credentials: Credentials, version: ApiVersion, user_id: String },
+    /// OAuth 2.0 user context. always goes through v2.
+    #[cfg(feature = "x-v2")]
+    OAuth2 { session: oauth2::Session, user_id: String },
+    /// answers like X would, including 429s with x-rate-limit-reset, without any network.
+    Simulated { clock: SharedClock, window: Mutex<(DateTime<Utc>, u32)> },
+    /// answers with the responses of a --record session, in order.
+    Replayed { replay: Replay, version: ApiVersion },
+}
+
+impl Api {
+    pub fn simulated(clock: SharedClock) -> Self {
+        let now = clock.now();
+        let start = DateTime::from_timestamp(now.timestamp(), 0).unwrap_or(now);
+        Api::Simulated { clock, window: Mutex::new((start, 0)) }
+    }
+
+    /// method and url of `delete`. the simulated API stands in for v1.1.
+    pub fn request(&self, id: u64, action: Action) -> (Method, String) {
+        match self {
+            Api::X { version, user_id, .. } => endpoint(id, action, *version, user_id),
+            #[cfg(feature = "x-v2")]
+            Api::OAuth2 { user_id, .. } => endpoint(id, action, ApiVersion::V2, user_id),
+            Api::Simulated { .. } => endpoint(id, action, ApiVersion::default(), "simulated"),
+            // as they were recorded.
+            Api::Replayed { version, .. } => endpoint(id, action, *version, record::USER_ID),
+        }
+    }
+
+    pub async fn delete(&self, id: u64, action: Action) -> anyhow::Result<Response> {
+        match self {
+            Api::X { credentials, version, user_id } => {
+                let (method, url) = endpoint(id, action, *version, user_id);
+                Ok(delete_tweet(method, &url, credentials).await?)
+            },
+            #[cfg(feature = "x-v2")]
+            Api::OAuth2 { session, user_id } => {
+                let (method, url) = endpoint(id, action, ApiVersion::V2, user_id);
+                session.send(method, &url).await
+            },
+            Api::Replayed { replay, .. } => {
+                let (method, url) = self.request(id, action);
+                replay.next(&method, &url)
+            },
+            Api::Simulated { clock, window } => {
+                let now = clock.now();
+                let mut window = window.lock().expect("window lock poisoned.");
+                if now - window.0 >= SIMULATED_WINDOW {
+                    // whole seconds, like the reset header, so waiting until the reset opens a new window.
+                    *window = (DateTime::from_timestamp(now.timestamp(), 0).unwrap_or(now), 0);
+                }
+                let reset = window.0 + SIMULATED_WINDOW;
+                let response = if window.1 < SIMULATED_LIMIT {
+                    window.1 += 1;
+                    http::Response::builder().status(200).body(format!("{{\"id_str\":\"{}\"}}", id))
+                } else {
+                    http::Response::builder().status(429).header("x-rate-limit-reset", reset.timestamp().to_string()).body(String::new())
+                };
+                Ok(Response::from(response.expect("valid simulated response.")))
+            },
+        }
+    }
+}
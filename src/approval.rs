@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashSet, fs::{File, OpenOptions}, io::{BufRead, BufReader, Write}, path::Path};
+
+use crate::{backup::sha256_hex, plan, schema, thread};
+
+/// one line of the approvals file: someone other than the author looked at a plan and let it run.
+#[derive(Serialize, Deserialize)]
+struct Approval {
+    #[serde(default)]
+    schema_version: u32,
+    plan_hash: String,
+    approver: String,
+    approved_at: DateTime<Utc>,
+    /// the posts the hash covers, so a partly applied plan still matches its approval.
+    ids: Vec<u64>,
+}
+
+fn sorted_ids(posts: &[Value]) -> Vec<u64> {
+    let mut ids: Vec<u64> = posts.iter().filter_map(thread::id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// what approvals refer to a plan by: SHA-256 over its post ids, sorted and one per line.
+/// the ids alone decide what gets deleted, so the order of the file and its metadata don't count.
+pub fn plan_hash(posts: &[Value]) -> String {
+    let lines: String = sorted_ids(posts).iter().map(|id| format!("{}\n", id)).collect();
+    sha256_hex(lines.as_bytes())
+}
+
+/// records the approval of the plan at `path` by `approver`. with `expected`, the plan has to
+/// still be the one the approver reviewed.
+pub fn approve(path: &Path, approver: &str, expected: Option<&str>, approvals: &Path) -> Result<()> {
+    let (posts, _) = plan::read(path)?;
+    let hash = plan_hash(&posts);
+    if let Some(expected) = expected {
+        anyhow::ensure!(expected.eq_ignore_ascii_case(&hash), "plan changed since it was reviewed. path={} expected={} actual={}", path.display(), expected, hash);
+    }
+    let approval = Approval { schema_version: schema::APPROVAL, plan_hash: hash.clone(), approver: approver.to_string(), approved_at: Utc::now(), ids: sorted_ids(&posts) };
+    let mut file = OpenOptions::new().create(true).append(true).open(approvals)
+        .with_context(|| format!("failed to open approvals. path={}", approvals.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&approval)?)?;
+    println!("approved. path={} plan_hash={} posts={} approver={}", path.display(), hash, posts.len(), approver);
+    Ok(())
+}
+
+fn read(approvals: &Path) -> Result<Vec<Approval>> {
+    let file = File::open(approvals).with_context(|| format!("failed to open approvals. path={}", approvals.display()))?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let approval: Approval = serde_json::from_str(&line)
+            .with_context(|| format!("invalid approvals line. path={} line={}", approvals.display(), index + 1))?;
+        anyhow::ensure!(approval.schema_version <= schema::APPROVAL, "approvals file was written by a newer post_remove. path={}", approvals.display());
+        records.push(approval);
+    }
+    Ok(records)
+}
+
+/// refuses `posts` unless an approval covers every one of them: the plan as approved, or what's left of it.
+pub fn check(posts: &[Value], approvals: &Path) -> Result<()> {
+    let hash = plan_hash(posts);
+    let ids = sorted_ids(posts);
+    let approval = read(approvals)?.into_iter().find(|approval| {
+        let approved: HashSet<u64> = approval.ids.iter().copied().collect();
+        approval.plan_hash == hash || ids.iter().all(|id| approved.contains(id))
+    });
+    let approval = approval.with_context(|| format!("plan isn't approved. run approve on it first. plan_hash={} approvals={}", hash, approvals.display()))?;
+    println!("plan approved. plan_hash={} approver={} approved_at={}", approval.plan_hash, approval.approver, approval.approved_at);
+    Ok(())
+}
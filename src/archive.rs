@@ -0,0 +1,343 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use oauth1::{authorize, Token};
+use serde_json::Value;
+use std::{borrow::Cow, collections::{BTreeMap, HashMap}, fs::{self, File}, io::{BufReader, Read}, path::{Path, PathBuf}};
+
+use crate::{cli::{AuthMethod, RunOptions}, parse_tweets_data, thread, Credentials, Error};
+#[cfg(feature = "x-v2")]
+use crate::oauth2;
+
+const USER_TIMELINE: &str = "https://api.x.com/1.1/statuses/user_timeline.json";
+
+pub fn is_zip(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+/// on Windows, `path` in its `\\?\` form, so the files of an archive unpacked deep in a folder
+/// aren't cut off at MAX_PATH. elsewhere, and for globs, `path` as it is.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        use std::{ffi::OsString, os::windows::ffi::{OsStrExt, OsStringExt}};
+
+        let absolute = match std::path::absolute(path) {
+            Ok(absolute) => absolute,
+            Err(_) => return Cow::Borrowed(path),
+        };
+        let wide: Vec<u16> = absolute.as_os_str().encode_wide().collect();
+        let is = |prefix: &str| wide.starts_with(&prefix.encode_utf16().collect::<Vec<_>>());
+        // a glob would read the `?` of the prefix as a wildcard.
+        if is(r"\\?\") || is(r"\\.\") || path.to_string_lossy().contains(['*', '?', '[']) {
+            return Cow::Borrowed(path);
+        }
+        let (prefix, rest) = match is(r"\\") {
+            true => (r"\\?\UNC\", &wide[2..]),
+            false => (r"\\?\", &wide[..]),
+        };
+        let verbatim: Vec<u16> = prefix.encode_utf16().chain(rest.iter().copied()).collect();
+        Cow::Owned(PathBuf::from(OsString::from_wide(&verbatim)))
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// `archiveInfo.generationDate` of the archive's manifest.js, found next to tweets.json, in `data/`
+/// of an archive directory, or inside the zip.
+pub fn generation_date(tweets: &Path) -> Option<DateTime<Utc>> {
+    let manifest = if is_zip(tweets) {
+        let mut zip = zip::ZipArchive::new(File::open(tweets).ok()?).ok()?;
+        let mut manifest = String::new();
+        zip.by_name("data/manifest.js").ok()?.read_to_string(&mut manifest).ok()?;
+        manifest
+    } else {
+        let dir = if tweets.is_dir() { tweets } else { tweets.parent()? };
+        ["manifest.js", "data/manifest.js", "manifest.json"].iter()
+            .find_map(|name| fs::read_to_string(dir.join(name)).ok())?
+    };
+    // manifest.js assigns the object to a global: `window.__THAR_CONFIG = {...}`.
+    let json = &manifest[manifest.find('{')?..];
+    let manifest: Value = serde_json::from_str(json.trim_end().trim_end_matches(';')).ok()?;
+    let date = manifest["archiveInfo"]["generationDate"].as_str()?;
+    DateTime::parse_from_rfc3339(date).ok().map(|date| date.to_utc())
+}
+
+/// 0 for `tweets.js`, N for `tweets-partN.js`. older archives call them tweet.js and tweet-partN.js.
+fn part_number(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.strip_suffix(".js").or_else(|| name.strip_suffix(".json"))?;
+    let rest = stem.strip_prefix("tweets").or_else(|| stem.strip_prefix("tweet"))?;
+    match rest {
+        "" => Some(0),
+        _ => rest.strip_prefix("-part")?.parse().ok(),
+    }
+}
+
+/// the tweets files `path` names, in part order: the path itself when it's a file, the parts in
+/// an archive directory (or its data/), or the matches of a glob such as `data/tweets-part*.js`.
+pub fn parts(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut parts: Vec<PathBuf> = if path.is_dir() {
+        let dir = if path.join("data").is_dir() { path.join("data") } else { path.to_path_buf() };
+        fs::read_dir(&dir).with_context(|| format!("failed to read {}.", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| part_number(path).is_some())
+            .collect()
+    } else if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    } else {
+        let pattern = path.to_string_lossy();
+        glob::glob(&pattern).with_context(|| format!("invalid glob. pattern={}", pattern))?
+            .filter_map(|path| path.ok())
+            .filter(|path| path.is_file())
+            .collect()
+    };
+    anyhow::ensure!(!parts.is_empty(), "no tweets files found. path={}", path.display());
+    // parts sort by number, anything else a glob matched after them by name.
+    parts.sort_by_key(|path| (part_number(path).unwrap_or(u32::MAX), path.clone()));
+    Ok(parts)
+}
+
+/// the parsed tweets parts under data/ of a downloaded archive zip, in part order, read straight
+/// from the zip without unpacking it.
+pub fn zip_parts(path: &Path) -> Result<Vec<(String, Vec<Value>)>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}.", path.display()))?;
+    let mut zip = zip::ZipArchive::new(file).with_context(|| format!("invalid zip. path={}", path.display()))?;
+    let mut names: Vec<String> = zip.file_names()
+        .filter(|name| name.starts_with("data/") && part_number(Path::new(name)).is_some())
+        .map(str::to_string)
+        .collect();
+    anyhow::ensure!(!names.is_empty(), "no data/tweets*.js in the zip. path={}", path.display());
+    names.sort_by_key(|name| (part_number(Path::new(name)), name.clone()));
+    let mut parts = Vec::new();
+    for name in names {
+        let entry = zip.by_name(&name).with_context(|| format!("failed to read {} from the zip.", name))?;
+        let Value::Array(entries) = parse_tweets_data(BufReader::new(entry)).map_err(|err| Error::parse(&name, err))? else {
+            anyhow::bail!("{} in the zip isn't a list of posts. path={}", name, path.display());
+        };
+        parts.push((name.trim_start_matches("data/").to_string(), entries));
+    }
+    Ok(parts)
+}
+
+/// what the rest of the tool calls the wrapper of a post in an archive entry.
+const WRAPPER: &str = "tweet";
+
+fn is_post(value: &Value) -> bool {
+    value.is_object() && (value["id_str"].is_string() || value["id"].is_string())
+}
+
+/// puts the post of every entry under `tweet`. archive generations differ in what they wrap posts
+/// in (`tweet`, `tweetObject`), so the wrapper is whichever key of the entry holds a post. bare
+/// posts get wrapped. anything else is refused with what it looked like, rather than read as empty.
+pub fn normalize(entries: &mut [Value], path: &Path) -> Result<()> {
+    let mut renamed: BTreeMap<String, usize> = BTreeMap::new();
+    for (index, entry) in entries.iter_mut().enumerate() {
+        if is_post(&entry[WRAPPER]) {
+            continue;
+        }
+        if is_post(entry) {
+            *entry = serde_json::json!({ WRAPPER: entry.take() });
+            *renamed.entry(String::new()).or_default() += 1;
+            continue;
+        }
+        let Some(object) = entry.as_object_mut() else {
+            anyhow::bail!("unexpected archive entry. expect an object. path={} index={} entry={}", path.display(), index, entry);
+        };
+        let Some(key) = object.iter().find(|(_, value)| is_post(value)).map(|(key, _)| key.clone()) else {
+            let keys: Vec<&str> = object.keys().map(String::as_str).collect();
+            anyhow::bail!("unexpected archive entry. expect a post under `tweet` or `tweetObject`. path={} index={} keys=[{}]", path.display(), index, keys.join(","));
+        };
+        let post = object.remove(&key).expect("found above.");
+        object.insert(WRAPPER.to_string(), post);
+        *renamed.entry(key).or_default() += 1;
+    }
+    for (key, posts) in renamed {
+        match key.as_str() {
+            "" => println!("read unwrapped posts. path={} posts={}", path.display(), posts),
+            key => println!("read posts wrapped in {}. path={} posts={}", key, path.display(), posts),
+        }
+    }
+    Ok(())
+}
+
+/// a v1.1 status in the archive's layout: the numbers the archive keeps as strings become strings.
+fn archive_shape(mut status: Value) -> Value {
+    for (key, string_key) in [("id", "id_str"), ("in_reply_to_status_id", "in_reply_to_status_id_str")] {
+        if let Some(id) = status[string_key].as_str().map(str::to_string) {
+            status[key] = Value::String(id);
+        }
+    }
+    for key in ["favorite_count", "retweet_count"] {
+        if let Some(count) = status[key].as_u64() {
+            status[key] = Value::String(count.to_string());
+        }
+    }
+    serde_json::json!({ "tweet": status })
+}
+
+/// pages through the authenticated user's timeline for posts newer than `since_id`, newest first.
+pub async fn posts_since(credentials: &Credentials, since_id: u64) -> Result<Vec<Value>> {
+    let client = reqwest::Client::new();
+    let consumer = Token::new(&credentials.consumer_key, &credentials.consumer_secret);
+    let access = Token::new(&credentials.access_key, &credentials.access_secret);
+    let mut posts = Vec::new();
+    let mut max_id: Option<u64> = None;
+    loop {
+        let mut query: Vec<(&str, String)> = vec![
+            ("count", "200".to_string()),
+            ("since_id", since_id.to_string()),
+            ("tweet_mode", "extended".to_string()),
+            ("include_rts", "true".to_string()),
+        ];
+        if let Some(max_id) = max_id {
+            query.push(("max_id", max_id.to_string()));
+        }
+        let params: HashMap<&str, Cow<str>> = query.iter().map(|(key, value)| (*key, Cow::from(value.as_str()))).collect();
+        let authorize_header = authorize("GET", USER_TIMELINE, &consumer, Some(&access), Some(params));
+        let page: Vec<Value> = client.get(USER_TIMELINE)
+            .query(&query)
+            .header("Authorization", authorize_header)
+            .send()
+            .await?
+            .error_for_status()
+            .context("failed to fetch the timeline.")?
+            .json()
+            .await?;
+        let Some(oldest) = page.iter().filter_map(|status| status["id_str"].as_str()?.parse::<u64>().ok()).min() else {
+            break;
+        };
+        posts.extend(page.into_iter().map(archive_shape));
+        println!("fetched. posts={}", posts.len());
+        max_id = Some(oldest - 1);
+    }
+    Ok(posts)
+}
+
+/// a v2 post in the archive's layout, with the fields `posts_since_v2` asks for.
+#[cfg(feature = "x-v2")]
+fn v2_archive_shape(post: &Value) -> Option<Value> {
+    let id = post["id"].as_str()?;
+    let created_at = post["created_at"].as_str()
+        .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+        .map(|created_at| created_at.to_utc().format("%a %b %d %H:%M:%S %z %Y").to_string());
+    let metric = |key: &str| post["public_metrics"][key].as_u64().unwrap_or(0).to_string();
+    let referenced = |kind: &str| post["referenced_tweets"].as_array().into_iter().flatten()
+        .find(|referenced| referenced["type"] == kind)
+        .and_then(|referenced| referenced["id"].as_str());
+    let mut tweet = serde_json::json!({
+        "id": id,
+        "id_str": id,
+        "full_text": post["text"],
+        "created_at": created_at,
+        "favorite_count": metric("like_count"),
+        "retweet_count": metric("retweet_count"),
+    });
+    if let Some(parent) = referenced("replied_to") {
+        tweet["in_reply_to_status_id"] = parent.into();
+        tweet["in_reply_to_status_id_str"] = parent.into();
+    }
+    if let Some(source) = referenced("retweeted") {
+        tweet["retweeted_status"] = serde_json::json!({ "id": source, "id_str": source });
+    }
+    Some(serde_json::json!({ "tweet": tweet }))
+}
+
+/// `posts_since` for --auth oauth2, through GET /2/users/:id/tweets.
+#[cfg(feature = "x-v2")]
+async fn posts_since_v2(session: &oauth2::Session, since_id: u64) -> Result<Vec<Value>> {
+    let account = session.account().await?;
+    let mut posts = Vec::new();
+    let mut pagination_token: Option<String> = None;
+    loop {
+        let mut url = reqwest::Url::parse(&format!("https://api.x.com/2/users/{}/tweets", account.id))?;
+        url.query_pairs_mut()
+            .append_pair("max_results", "100")
+            .append_pair("since_id", &since_id.to_string())
+            .append_pair("tweet.fields", "created_at,public_metrics,referenced_tweets");
+        if let Some(token) = &pagination_token {
+            url.query_pairs_mut().append_pair("pagination_token", token);
+        }
+        let page: Value = session.send(reqwest::Method::GET, url.as_str()).await?
+            .error_for_status()
+            .context("failed to fetch the timeline.")?
+            .json()
+            .await?;
+        posts.extend(page["data"].as_array().into_iter().flatten().filter_map(v2_archive_shape));
+        match page["meta"]["next_token"].as_str() {
+            Some(token) => pagination_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+    Ok(posts)
+}
+
+/// warns about the posts an archive can't contain and, with `supplement`, fetches them into `entries`
+/// with the --auth of `options`.
+pub async fn fill_gap(tweets: &Path, entries: &mut Vec<Value>, supplement: bool, options: &RunOptions) -> Result<()> {
+    match (generation_date(tweets), supplement) {
+        (None, false) => Ok(()),
+        (Some(generated_at), false) => {
+            eprintln!("{} the archive was generated at {}. posts created after it aren't in it and won't be processed. pass --supplement-api to fetch them.", crate::color::warning(), generated_at);
+            Ok(())
+        },
+        (generated_at, true) => {
+            if let Some(generated_at) = generated_at {
+                println!("archive generated at {}. fetching newer posts.", generated_at);
+            }
+            fetch_into(entries, options).await
+        },
+    }
+}
+
+async fn fetch_into(entries: &mut Vec<Value>, options: &RunOptions) -> Result<()> {
+    let since_id = entries.iter().filter_map(thread::id).max().unwrap_or(1);
+    let fetched = match options.auth {
+        AuthMethod::Oauth1 => posts_since(&Credentials::from_env()?, since_id).await?,
+        #[cfg(feature = "x-v2")]
+        AuthMethod::Oauth2 => posts_since_v2(&oauth2::Session::load(&options.oauth2_token_file)?, since_id).await?,
+    };
+    println!("supplemented. posts={}", fetched.len());
+    // entries are newest first in the archive, so the fetched posts go in front.
+    entries.splice(0..0, fetched);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn every_post_ends_up_under_tweet() {
+        let mut entries = vec![
+            json!({ "tweet": { "id_str": "1" } }),
+            json!({ "tweetObject": { "id_str": "2" } }),
+            json!({ "id": "3", "full_text": "bare" }),
+        ];
+        normalize(&mut entries, Path::new("tweets.js")).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|entry| entry["tweet"]["id_str"].as_str().or(entry["tweet"]["id"].as_str()).unwrap()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert!(entries[1].get("tweetObject").is_none());
+    }
+
+    #[cfg(feature = "x-v2")]
+    #[test]
+    fn v2_posts_take_the_archive_layout() {
+        let post = json!({
+            "id": "5", "text": "RT @friend: hi", "created_at": "2024-01-02T03:04:05.000Z",
+            "public_metrics": { "like_count": 2, "retweet_count": 1 },
+            "referenced_tweets": [{ "type": "replied_to", "id": "4" }, { "type": "retweeted", "id": "3" }],
+        });
+        let entry = v2_archive_shape(&post).unwrap();
+        assert_eq!(entry["tweet"]["created_at"], "Tue Jan 02 03:04:05 +0000 2024");
+        assert_eq!(entry["tweet"]["favorite_count"], "2");
+        assert_eq!(thread::parent_id(&entry), Some(4));
+        assert_eq!(entry["tweet"]["retweeted_status"]["id_str"], "3");
+    }
+
+    #[test]
+    fn refuses_entries_without_a_post() {
+        assert!(normalize(&mut [json!({ "note": { "text": "no id" } })], Path::new("tweets.js")).is_err());
+        assert!(normalize(&mut [json!("1")], Path::new("tweets.js")).is_err());
+    }
+}
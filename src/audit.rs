@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use crate::{clock::SharedClock, ledger::Rotation, report, text};
+
+/// one line of the --audit-log: a deletion that was attempted, whatever came of it.
+#[derive(Serialize)]
+pub struct AuditRecord<'a> {
+    pub schema_version: u32,
+    pub id: u64,
+    pub attempted_at: DateTime<Utc>,
+    pub action: &'static str,
+    /// the start of the text, or its hash with --redact-text.
+    pub text: String,
+    /// of the last response. none when the request never got one.
+    pub status: Option<u16>,
+    pub outcome: &'a str,
+    pub run_id: &'a str,
+}
+
+/// what was removed and when, for users purging for compliance. unlike the ledger, every attempt
+/// gets a line, a retried post one per try and a rate-limited request one with outcome
+/// rate-limited, and each line is synced to disk before the run goes on.
+pub struct AuditLog {
+    path: PathBuf,
+    rotation: Rotation,
+    redact_text: bool,
+    run_id: String,
+    clock: SharedClock,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf, rotation: Rotation, redact_text: bool, run_id: &str, clock: SharedClock) -> Self {
+        Self { path, rotation, redact_text, run_id: run_id.to_string(), clock }
+    }
+
+    /// `tweet` is the `tweet` object of the entry.
+    pub fn append(&self, id: u64, tweet: &Value, action: &'static str, status: Option<u16>, outcome: &str) -> Result<()> {
+        let text = if self.redact_text { text::redact(text::of(tweet)) } else { report::preview(text::of(tweet)) };
+        let record = AuditRecord { schema_version: crate::schema::AUDIT, id, attempted_at: self.clock.now(), action, text, status, outcome, run_id: &self.run_id };
+        self.rotation.rotate_if_needed(&self.path)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("failed to open audit log. path={}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        file.sync_data().with_context(|| format!("failed to sync audit log. path={}", self.path.display()))?;
+        Ok(())
+    }
+}
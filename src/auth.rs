@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use oauth1::{authorize, Token};
+use percent_encoding::percent_decode_str;
+use std::{borrow::Cow, collections::HashMap, env, fs, io::{self, IsTerminal, Write}, path::Path};
+
+const REQUEST_TOKEN: &str = "https://api.x.com/oauth/request_token";
+const AUTHORIZE: &str = "https://api.x.com/oauth/authorize";
+const ACCESS_TOKEN: &str = "https://api.x.com/oauth/access_token";
+
+/// `key=value&...` bodies of the oauth endpoints.
+fn parse_form(body: &str) -> HashMap<String, String> {
+    let decode = |part: &str| percent_decode_str(&part.replace('+', " ")).decode_utf8_lossy().into_owned();
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect()
+}
+
+async fn token_request(url: &str, consumer: &Token<'_>, token: Option<&Token<'_>>, params: HashMap<&str, Cow<'_, str>>) -> Result<HashMap<String, String>> {
+    let authorize_header = authorize("POST", url, consumer, token, Some(params));
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Authorization", authorize_header)
+        .send()
+        .await
+        .with_context(|| format!("failed to reach the API. url={}", url))?;
+    let status = response.status();
+    let body = response.text().await?;
+    anyhow::ensure!(status.is_success(), "token request rejected. url={} status={} body={}", url, status, body);
+    Ok(parse_form(&body))
+}
+
+/// sets `key=value` in the dotenv file, replacing an existing line of the same key.
+fn set_vars(path: &Path, vars: &[(&str, &str)]) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}.", path.display())),
+    };
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    for (key, value) in vars {
+        let line = format!("{}={}", key, value);
+        let prefix = format!("{}=", key);
+        let found = lines.iter_mut().find(|line| {
+            let line = line.trim_start();
+            line.strip_prefix("export ").unwrap_or(line).starts_with(&prefix)
+        });
+        match found {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, lines.join("\n") + "\n").with_context(|| format!("failed to write {}.", temporary.display()))?;
+    // the file holds secrets.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temporary, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&temporary, path).with_context(|| format!("failed to write {}.", path.display()))?;
+    Ok(())
+}
+
+/// PIN-based OAuth 1.0a: gets a request token, lets the user authorize the app in a browser,
+/// trades the PIN for an access token and stores it as ACCESS_KEY/ACCESS_SECRET in `env_file`.
+pub async fn run(env_file: &Path) -> Result<()> {
+    let consumer_key = env::var("CONSUMER_KEY").context("CONSUMER_KEY not found in environment.")?;
+    let consumer_secret = env::var("CONSUMER_SECRET").context("CONSUMER_SECRET not found in environment.")?;
+    anyhow::ensure!(io::stdin().is_terminal(), "auth needs a terminal to read the PIN from.");
+    let consumer = Token::new(consumer_key.as_str(), consumer_secret.as_str());
+
+    let request = token_request(REQUEST_TOKEN, &consumer, None, HashMap::from([("oauth_callback", Cow::from("oob"))])).await?;
+    let (Some(request_key), Some(request_secret)) = (request.get("oauth_token"), request.get("oauth_token_secret")) else {
+        anyhow::bail!("request token missing from the response. url={}", REQUEST_TOKEN);
+    };
+    println!("open this url, authorize the app and enter the PIN shown.");
+    println!("{}?oauth_token={}", AUTHORIZE, request_key);
+    print!("PIN: ");
+    io::stdout().flush()?;
+    let mut pin = String::new();
+    io::stdin().read_line(&mut pin)?;
+    let pin = pin.trim();
+    anyhow::ensure!(!pin.is_empty(), "no PIN entered.");
+
+    let request_token = Token::new(request_key.as_str(), request_secret.as_str());
+    let access = token_request(ACCESS_TOKEN, &consumer, Some(&request_token), HashMap::from([("oauth_verifier", Cow::from(pin))])).await?;
+    let (Some(access_key), Some(access_secret)) = (access.get("oauth_token"), access.get("oauth_token_secret")) else {
+        anyhow::bail!("access token missing from the response. url={}", ACCESS_TOKEN);
+    };
+    set_vars(env_file, &[("ACCESS_KEY", access_key), ("ACCESS_SECRET", access_secret)])?;
+    println!("authorized. id={} screen_name={} env={}",
+        access.get("user_id").map(String::as_str).unwrap_or_default(),
+        access.get("screen_name").map(String::as_str).unwrap_or_default(),
+        env_file.display());
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{fs::{self, File}, io::Write, path::{Path, PathBuf}, sync::Arc};
+use tokio::{sync::{mpsc, Semaphore}, task::JoinHandle};
+
+use crate::{ledger::LedgerRecord, rehost::Rehost, schema};
+
+#[derive(Serialize, Deserialize)]
+pub struct MediaFile {
+    pub url: String,
+    pub file: String,
+    pub size: u64,
+    pub sha256: String,
+    /// where --rehost put a copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rehosted_url: Option<String>,
+}
+
+/// a t.co link of the post and where it led. the mapping dies with the post.
+#[derive(Serialize, Deserialize)]
+pub struct Link {
+    pub url: String,
+    /// None when the archive didn't say and t.co couldn't be asked.
+    pub expanded_url: Option<String>,
+}
+
+/// `<backup-dir>/<id>/post.json`, written before the post is deleted.
+#[derive(Serialize, Deserialize)]
+pub struct BackupRecord {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub id: u64,
+    pub backed_up_at: DateTime<Utc>,
+    pub tweet: Value,
+    pub media: Vec<MediaFile>,
+    #[serde(default)]
+    pub links: Vec<Link>,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// the file name part of a media url without the query string.
+fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// photo urls, plus the best mp4 variant of videos and GIFs.
+pub fn media_urls(tweet: &Value) -> Vec<String> {
+    let media = tweet["extended_entities"]["media"].as_array()
+        .or_else(|| tweet["entities"]["media"].as_array());
+    let mut urls = Vec::new();
+    for item in media.into_iter().flatten() {
+        let best_variant = item["video_info"]["variants"].as_array().and_then(|variants| {
+            variants.iter()
+                .filter(|variant| variant["content_type"] == "video/mp4")
+                .max_by_key(|variant| variant["bitrate"].as_str().and_then(|bitrate| bitrate.parse::<u64>().ok()).unwrap_or(0))
+                .and_then(|variant| variant["url"].as_str())
+        });
+        if let Some(url) = best_variant.or_else(|| item["media_url_https"].as_str()) {
+            if !urls.iter().any(|known| known == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+fn write_durably(path: &Path, bytes: &[u8]) -> Result<()> {
+    let temporary = PathBuf::from(format!("{}.tmp", path.display()));
+    let mut file = File::create(&temporary).with_context(|| format!("failed to create {}.", temporary.display()))?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path).with_context(|| format!("failed to write {}.", path.display()))?;
+    // the rename itself is only durable once the directory is synced.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+pub struct Backup {
+    dir: PathBuf,
+    // `tweets_media` of an unpacked archive. media found there isn't downloaded again.
+    archive_media: Option<PathBuf>,
+    rehost: Option<Rehost>,
+    client: reqwest::Client,
+    // doesn't follow redirects, so t.co answers with the destination in Location.
+    resolver: reqwest::Client,
+}
+
+impl Backup {
+    pub fn new(dir: PathBuf, archive_media: Option<PathBuf>, rehost: Option<Rehost>) -> Self {
+        let resolver = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("failed to build http client.");
+        Self { dir, archive_media, rehost, client: reqwest::Client::new(), resolver }
+    }
+
+    async fn fetch(&self, id: u64, url: &str) -> Result<Vec<u8>> {
+        if let Some(archive_media) = &self.archive_media {
+            let local = archive_media.join(format!("{}-{}", id, file_name(url)));
+            if let Ok(bytes) = fs::read(&local) {
+                return Ok(bytes);
+            }
+        }
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn resolve(&self, url: &str) -> Option<String> {
+        let response = self.resolver.head(url).send().await.ok()?;
+        response.headers().get("Location")?.to_str().ok().map(str::to_string)
+    }
+
+    /// t.co links of the entities, plus any only found in the text. expanded_url comes from the
+    /// archive when it has one, otherwise from asking t.co.
+    async fn links(&self, tweet: &Value) -> Vec<Link> {
+        let mut links: Vec<Link> = Vec::new();
+        let entities = tweet["entities"]["urls"].as_array().into_iter().flatten()
+            .chain(tweet["entities"]["media"].as_array().into_iter().flatten());
+        for entity in entities {
+            if let Some(url) = entity["url"].as_str().filter(|url| !links.iter().any(|link| link.url == *url)) {
+                links.push(Link { url: url.to_string(), expanded_url: entity["expanded_url"].as_str().map(str::to_string) });
+            }
+        }
+        let full_text = crate::text::of(tweet);
+        for word in full_text.split_whitespace().filter(|word| word.starts_with("https://t.co/") || word.starts_with("http://t.co/")) {
+            if !links.iter().any(|link| link.url == word) {
+                links.push(Link { url: word.to_string(), expanded_url: None });
+            }
+        }
+        for link in links.iter_mut().filter(|link| link.expanded_url.is_none()) {
+            link.expanded_url = self.resolve(&link.url).await;
+            if link.expanded_url.is_none() {
+                eprintln!("{} failed to resolve link. url={}", crate::color::warning(), link.url);
+            }
+        }
+        links
+    }
+
+    /// writes the post and its media. the post is only safe to delete once this returns Ok.
+    pub async fn save(&self, id: u64, tweet: &Value) -> Result<()> {
+        let post_dir = self.dir.join(id.to_string());
+        fs::create_dir_all(&post_dir).with_context(|| format!("failed to create {}.", post_dir.display()))?;
+        let mut media = Vec::new();
+        for url in media_urls(tweet) {
+            let bytes = self.fetch(id, &url).await.with_context(|| format!("failed to fetch media. id={} url={}", id, url))?;
+            let file = file_name(&url).to_string();
+            write_durably(&post_dir.join(&file), &bytes)?;
+            let rehosted_url = match &self.rehost {
+                Some(rehost) => Some(rehost.upload(&self.client, &format!("{}/{}", id, file), &bytes).await
+                    .with_context(|| format!("failed to rehost media. id={} file={}", id, file))?),
+                None => None,
+            };
+            media.push(MediaFile { url, file, size: bytes.len() as u64, sha256: sha256_hex(&bytes), rehosted_url });
+        }
+        let links = self.links(tweet).await;
+        let record = BackupRecord { schema_version: schema::BACKUP, id, backed_up_at: Utc::now(), tweet: tweet.clone(), media, links };
+        write_durably(&post_dir.join("post.json"), &serde_json::to_vec_pretty(&record)?)
+    }
+}
+
+/// backs posts up ahead of the deleter, `concurrency` at a time and at most `queue` posts ahead,
+/// so slow media downloads overlap with the rate-limited deletions.
+pub struct Pipeline {
+    results: mpsc::Receiver<JoinHandle<(u64, Result<()>)>>,
+}
+
+impl Pipeline {
+    /// `posts` must be in the order the deleter will ask for them.
+    pub fn start(backup: Backup, posts: impl Iterator<Item = (u64, Value)> + Send + 'static, concurrency: usize, queue: usize) -> Self {
+        let (sender, results) = mpsc::channel(queue.max(1));
+        let backup = Arc::new(backup);
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        tokio::spawn(async move {
+            for (id, tweet) in posts {
+                let permit = semaphore.clone().acquire_owned().await.expect("semaphore closed.");
+                let backup = backup.clone();
+                let task = tokio::spawn(async move {
+                    let result = backup.save(id, &tweet).await;
+                    drop(permit);
+                    (id, result)
+                });
+                // the deleter stopped.
+                if sender.send(task).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Self { results }
+    }
+
+    /// waits for the backup of `id`, the next post in order. only Ok means the post may be deleted.
+    pub async fn wait(&mut self, id: u64) -> Result<()> {
+        let task = self.results.recv().await.context("backup pipeline ended early.")?;
+        let (backed_up, result) = task.await.context("backup task panicked.")?;
+        anyhow::ensure!(backed_up == id, "backup pipeline out of order. expected={} actual={}", id, backed_up);
+        result
+    }
+}
+
+pub fn read_record(dir: &Path, id: u64) -> Result<BackupRecord> {
+    let path = dir.join(id.to_string()).join("post.json");
+    let file = File::open(&path).with_context(|| format!("backup not found. path={}", path.display()))?;
+    let mut record: Value = serde_json::from_reader(file).with_context(|| format!("invalid backup. path={}", path.display()))?;
+    schema::migrate_backup_record(&mut record).with_context(|| format!("unsupported backup. path={}", path.display()))?;
+    serde_json::from_value(record).with_context(|| format!("invalid backup. path={}", path.display()))
+}
+
+/// checks every deleted post of the ledger against its backup. returns the number of problems.
+pub fn verify(records: &[LedgerRecord], dir: &Path) -> usize {
+    let mut problems = 0;
+    let mut checked = 0;
+    for record in records.iter().filter(|record| record.outcome == "deleted") {
+        checked += 1;
+        let backup = match read_record(dir, record.id) {
+            Ok(backup) => backup,
+            Err(err) => {
+                println!("missing. id={} err={:#}", record.id, err);
+                problems += 1;
+                continue;
+            },
+        };
+        for media in &backup.media {
+            let path = dir.join(record.id.to_string()).join(&media.file);
+            match fs::read(&path) {
+                Ok(bytes) if bytes.len() as u64 != media.size => {
+                    println!("size mismatch. id={} file={} expected={} actual={}", record.id, media.file, media.size, bytes.len());
+                    problems += 1;
+                },
+                Ok(bytes) if sha256_hex(&bytes) != media.sha256 => {
+                    println!("hash mismatch. id={} file={}", record.id, media.file);
+                    problems += 1;
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    println!("media missing. id={} file={} err={}", record.id, media.file, err);
+                    problems += 1;
+                },
+            }
+        }
+    }
+    println!("verified. deleted={} problems={}", checked, problems);
+    problems
+}
+
+/// every backup under `dir`, oldest post first.
+pub fn read_all(dir: &Path) -> Result<Vec<BackupRecord>> {
+    let mut records = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}.", dir.display()))? {
+        let entry = entry?;
+        let Some(id) = entry.file_name().to_str().and_then(|name| name.parse::<u64>().ok()) else {
+            continue;
+        };
+        if entry.path().join("post.json").is_file() {
+            records.push(read_record(dir, id)?);
+        }
+    }
+    records.sort_by_key(|record| record.id);
+    Ok(records)
+}
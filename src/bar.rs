@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::{io::{self, IsTerminal}, sync::Mutex};
+
+use crate::{clock::SharedClock, timezone::Zone};
+
+/// the --progress-bar on stderr. rate and ETA go by the run's clock, so a simulated run shows
+/// where the real one would be.
+pub struct Bar {
+    bar: ProgressBar,
+    clock: SharedClock,
+    zone: Zone,
+    started: DateTime<Utc>,
+    /// processed when the run started, so a resumed run's rate is its own.
+    initial: u64,
+    waits: Mutex<Waits>,
+}
+
+#[derive(Default)]
+struct Waits {
+    total: std::time::Duration,
+    current: Option<(String, DateTime<Utc>)>,
+}
+
+impl Bar {
+    /// none unless stderr is a terminal.
+    pub fn new(processed: usize, total: usize, clock: SharedClock, zone: Zone) -> Option<Self> {
+        if !io::stderr().is_terminal() {
+            return None;
+        }
+        let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+        bar.set_style(ProgressStyle::with_template("{bar:30} {pos}/{len} {msg}").expect("valid template."));
+        bar.set_position(processed as u64);
+        // redrawn while the run waits, so the bar doesn't look stuck.
+        bar.enable_steady_tick(std::time::Duration::from_secs(1));
+        let started = clock.now();
+        let bar = Self { bar, clock, zone, started, initial: processed as u64, waits: Mutex::new(Waits::default()) };
+        bar.refresh();
+        Some(bar)
+    }
+
+    pub fn update(&self, processed: usize, total: usize) {
+        self.bar.set_length(total as u64);
+        self.bar.set_position(processed as u64);
+        self.refresh();
+    }
+
+    /// a rate-limit wait of `duration` has begun. counted in waited= right away.
+    pub fn waiting(&self, reason: &str, resumes_at: DateTime<Utc>, duration: std::time::Duration) {
+        {
+            let mut waits = self.waits.lock().expect("bar lock poisoned.");
+            waits.total += duration;
+            waits.current = Some((reason.to_string(), resumes_at));
+        }
+        self.refresh();
+    }
+
+    pub fn resumed(&self) {
+        self.waits.lock().expect("bar lock poisoned.").current = None;
+        self.refresh();
+    }
+
+    /// prints above the bar.
+    pub fn println(&self, line: std::fmt::Arguments) {
+        self.bar.suspend(|| println!("{}", line));
+    }
+
+    pub fn eprintln(&self, line: std::fmt::Arguments) {
+        self.bar.suspend(|| eprintln!("{}", line));
+    }
+
+    pub fn finish(&self) {
+        self.refresh();
+        self.bar.abandon();
+    }
+
+    fn refresh(&self) {
+        let waits = self.waits.lock().expect("bar lock poisoned.");
+        let now = self.clock.now();
+        let position = self.bar.position();
+        let done = position.saturating_sub(self.initial);
+        let elapsed = (now - self.started).to_std().unwrap_or_default();
+        let rate = if elapsed.is_zero() { 0.0 } else { done as f64 * 60.0 / elapsed.as_secs_f64() };
+        // average pace of this run so far, including rate-limit waits.
+        let eta = match (done > 0).then(|| elapsed.mul_f64(self.bar.length().unwrap_or(position).saturating_sub(position) as f64 / done as f64)) {
+            Some(left) => self.zone.format(now + chrono::Duration::from_std(left).unwrap_or_default(), "%Y-%m-%d %H:%M"),
+            None => "-".to_string(),
+        };
+        let mut message = format!("rate={:.1}/min waited={} eta={}", rate, crate::format_remaining(waits.total), eta);
+        if let Some((reason, resumes_at)) = &waits.current {
+            message.push_str(&format!(" waiting={} until={}", reason, self.zone.format(*resumes_at, "%H:%M:%S")));
+        }
+        self.bar.set_message(message);
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use reqwest::{Method, Url};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::{api::Action, cli::{AuthMethod, RunOptions}, engine::{run, Listing}, filter::Filter, get_state, new_clock, new_metadata, new_run_id, oauth2, parse_instant};
+
+/// the most the endpoint returns per page.
+const PAGE_SIZE: &str = "100";
+
+/// a bookmarked post as an archive entry. `bookmark` marks it, so the run removes the bookmark
+/// and leaves the post alone. the date is the post's, since X doesn't say when it was bookmarked.
+fn as_entry(post: &Value) -> Option<Value> {
+    let id = post["id"].as_str()?;
+    let created_at = post["created_at"].as_str()
+        .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+        .map(|created_at| created_at.to_utc().format("%a %b %d %H:%M:%S %z %Y").to_string());
+    Some(json!({
+        "bookmark": { "author_id": post["author_id"] },
+        "tweet": { "id": id, "id_str": id, "full_text": post["text"], "created_at": created_at },
+    }))
+}
+
+/// every bookmark of the account, newest first, through GET /2/users/:id/bookmarks.
+async fn list(session: &oauth2::Session, user_id: &str, listing: &Listing) -> Result<Vec<Value>> {
+    let mut entries = Vec::new();
+    let mut pagination_token: Option<String> = None;
+    loop {
+        let mut url = Url::parse(&format!("https://api.x.com/2/users/{}/bookmarks", user_id))?;
+        url.query_pairs_mut().append_pair("max_results", PAGE_SIZE).append_pair("tweet.fields", "created_at,author_id");
+        if let Some(token) = &pagination_token {
+            url.query_pairs_mut().append_pair("pagination_token", token);
+        }
+        let response = session.send(Method::GET, url.as_str()).await?;
+        if response.status().as_u16() == 429 {
+            println!("rate limited. listing bookmarks.");
+            listing.rate_limited(response.headers(), Action::Unbookmark).await;
+            continue;
+        }
+        let page: Value = response.error_for_status().context("failed to list bookmarks.")?.json().await?;
+        entries.extend(page["data"].as_array().into_iter().flatten().filter_map(as_entry));
+        match page["meta"]["next_token"].as_str() {
+            Some(token) => pagination_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+    println!("listed bookmarks. bookmarks={}", entries.len());
+    Ok(entries)
+}
+
+/// removes the bookmarks of posts created before `before`. the list is fetched once and kept in
+/// `state`, which is rewritten with whatever is left and resumed from while it exists.
+pub async fn remove(before: &str, state: &Path, options: &RunOptions) -> Result<()> {
+    anyhow::ensure!(matches!(options.auth, AuthMethod::Oauth2) || options.simulate, "bookmarks are only available with --auth oauth2.");
+    anyhow::ensure!(options.backup_dir.is_none(), "--backup-dir doesn't apply to bookmarks. the bookmarked posts aren't yours.");
+    let cutoff = parse_instant(before).with_context(|| format!("failed --before parse. (format %Y-%m-%d or RFC 3339) value={}", before))?;
+    let clock = new_clock(options);
+    // the listing's waits show up under the id of the run that follows it.
+    let run_id = new_run_id(clock.now());
+    let (entries, previous) = if state.exists() {
+        println!("resuming. path={}", state.display());
+        get_state(state)?
+    } else {
+        anyhow::ensure!(!options.simulate, "bookmarks can't be listed under --simulate. run once without it first.");
+        let session = oauth2::Session::load(&options.oauth2_token_file)?;
+        let account = session.account().await?;
+        let listing = Listing::new(options, &account.id, &run_id, clock.clone())?;
+        (list(&session, &account.id, &listing).await?, None)
+    };
+    let total = entries.len();
+    let now = clock.now();
+    let older = Filter::created_before(cutoff);
+    let candidates: Vec<Value> = entries.into_iter().filter(|entry| older.matches(entry, now)).collect();
+    let mut metadata = new_metadata(Some(before.to_string()), vec!["bookmarks".to_string()], total, candidates.len());
+    metadata.run_id = run_id;
+    metadata.resume(previous);
+    run(candidates, state.to_path_buf(), metadata, options, clock).await
+}
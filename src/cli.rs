@@ -0,0 +1,723 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::color::ColorChoice;
+use crate::duplicates::KeepCopy;
+use crate::events::SinkSpec;
+use crate::filter::PostKind;
+use crate::timezone::Zone;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BatchBy {
+    Month,
+    /// the conversation of a direct message. what `dm` batches by unless told otherwise.
+    Conversation,
+}
+
+/// how requests are authorized.
+#[derive(Clone, Copy)]
+pub enum AuthMethod {
+    /// CONSUMER_KEY/CONSUMER_SECRET and ACCESS_KEY/ACCESS_SECRET.
+    Oauth1,
+    /// the token file of `auth --oauth2`, refreshed as it expires.
+    #[cfg(feature = "x-v2")]
+    Oauth2,
+}
+
+/// which X API deletes posts. only those of the x-v1 and x-v2 features the build has exist.
+#[derive(Clone, Copy)]
+pub enum ApiVersion {
+    /// POST /1.1/statuses/destroy/:id.json
+    #[cfg(feature = "x-v1")]
+    V1,
+    /// DELETE /2/tweets/:id, for apps that only have v2 access.
+    #[cfg(feature = "x-v2")]
+    V2,
+}
+
+impl ApiVersion {
+    /// v1.1 when the build has it.
+    pub const DEFAULT: &'static str = if cfg!(feature = "x-v1") { "v1.1" } else { "v2" };
+}
+
+impl Default for ApiVersion {
+    fn default() -> Self {
+        #[cfg(feature = "x-v1")]
+        let version = ApiVersion::V1;
+        #[cfg(not(feature = "x-v1"))]
+        let version = ApiVersion::V2;
+        version
+    }
+}
+
+/// by name, so one that isn't compiled in says so instead of being unknown.
+fn parse_api_version(value: &str) -> Result<ApiVersion, String> {
+    match value {
+        #[cfg(feature = "x-v1")]
+        "v1.1" => Ok(ApiVersion::V1),
+        #[cfg(feature = "x-v2")]
+        "v2" => Ok(ApiVersion::V2),
+        #[allow(unreachable_patterns)]
+        "v1.1" | "v2" => Err(crate::api::not_compiled(value)),
+        _ => Err(format!("expect v1.1 or v2. value={}", value)),
+    }
+}
+
+fn parse_auth(value: &str) -> Result<AuthMethod, String> {
+    match value {
+        "oauth1" => Ok(AuthMethod::Oauth1),
+        #[cfg(feature = "x-v2")]
+        "oauth2" => Ok(AuthMethod::Oauth2),
+        #[allow(unreachable_patterns)]
+        "oauth2" => Err(format!("oauth2 goes through v2. {}", crate::api::not_compiled("v2"))),
+        _ => Err(format!("expect oauth1 or oauth2. value={}", value)),
+    }
+}
+
+// which posts of an archive are candidates. shared by the default run and `plan`.
+#[derive(Args)]
+pub struct Selection {
+    /// path to tweets.json or tweets.js, an archive directory, a glob over tweets-partN.js whose
+    /// parts are merged, or the downloaded archive zip. the path is kept as the OS gave it, so
+    /// names that aren't UTF-8 are read as they are, and on Windows it's read in its long form,
+    /// past the 260-character limit.
+    #[arg(required = true)]
+    pub tweets: Option<PathBuf>,
+    /// deprecated spelling of --before, kept for existing scripts.
+    #[arg(hide = true, conflicts_with = "before")]
+    pub time: Option<String>,
+    /// select posts created before this instant. %Y-%m-%d is midnight UTC, RFC 3339 is exact.
+    #[arg(long, value_name = "DATE", required_unless_present_any = ["time", "older_than", "after", "ids_file", "dedupe", "delete_thread", "select_sql", "only", "keep_anniversaries", "decay", "expiry", "expiry_file"])]
+    pub before: Option<String>,
+    /// select posts older than this when the run starts, e.g. 90d or 6m, so a cron line never needs
+    /// a new date. units are s, min, h, d, w, m (30 days) and y.
+    #[arg(long, value_name = "DURATION", value_parser = parse_cooling_off, conflicts_with_all = ["time", "before"])]
+    pub older_than: Option<chrono::Duration>,
+    /// select posts created at or after this instant, whatever the other filters say. with --before
+    /// this deletes a window, e.g. `--after 2016-01-01 --before 2019-01-01` for 2016 to 2018.
+    #[arg(long, visible_alias = "not-before", value_name = "DATE")]
+    pub after: Option<String>,
+    /// select every repeat of a post with the same text except one copy. the cutoff is optional in this mode.
+    #[arg(long)]
+    pub dedupe: bool,
+    /// which copy of a repeated post survives --dedupe.
+    #[arg(long, value_enum, default_value = "earliest")]
+    pub keep_copy: KeepCopy,
+    /// only select link-share posts whose links now return 404/410.
+    #[arg(long)]
+    pub purge_dead_links: bool,
+    /// how many links --purge-dead-links checks at once.
+    #[arg(long, default_value_t = 8)]
+    pub link_check_concurrency: usize,
+    /// select exactly the posts in this file, one post url or bare id per line, instead of by date.
+    /// any cutoff is ignored. the other filters still apply.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["delete_thread", "dedupe"])]
+    pub ids_file: Option<PathBuf>,
+    /// never delete the posts in this file, one id (or post url) per line, e.g. the pinned post.
+    #[arg(long, value_name = "FILE")]
+    pub keep_ids: Option<PathBuf>,
+    /// leave the posts in this file alone for this run only, in the format of --keep-ids. unlike
+    /// the keep list, it's meant to be thrown away afterwards.
+    #[arg(long, value_name = "FILE")]
+    pub skip_ids_file: Option<PathBuf>,
+    /// also remove the likes of this like.js (or archive directory) in the same run. likes and posts
+    /// are sent in turns, by what each endpoint's rate limit has left, so under --daemon likes
+    /// go on while posts wait for their expiry.
+    #[arg(long, value_name = "PATH", conflicts_with = "batch_by")]
+    pub with_likes: Option<PathBuf>,
+    /// delete the whole self-reply chain rooted at this post, replies first.
+    #[arg(long, value_name = "ID", conflicts_with_all = ["time", "before", "older_than"])]
+    pub delete_thread: Option<u64>,
+    /// compare text with Unicode case folding. text is always NFC-normalized first.
+    #[arg(long)]
+    pub ignore_case: bool,
+    /// strip URLs, @mentions and emoji from the text before the text filters (--match, --exclude, --dedupe, --max-length) look at it.
+    #[arg(long)]
+    pub strip_entities: bool,
+    /// only select posts whose text is at most this many characters, e.g. 0 with --strip-entities
+    /// for posts that are only a link plus emoji.
+    #[arg(long, value_name = "CHARS")]
+    pub max_length: Option<usize>,
+    /// only select the ids returned by this query, e.g. "SELECT id FROM posts WHERE ...".
+    /// runs against an in-memory copy of the archive unless --select-db is given.
+    #[arg(long, value_name = "QUERY")]
+    pub select_sql: Option<String>,
+    /// database written by `export sqlite` to run --select-sql against.
+    #[arg(long, value_name = "DB", requires = "select_sql")]
+    pub select_db: Option<PathBuf>,
+    /// only select posts whose text matches this regex, e.g. "(?i)giveaway".
+    #[arg(long = "match", value_name = "REGEX")]
+    pub match_text: Option<String>,
+    /// never select posts whose text matches this regex.
+    #[arg(long, value_name = "REGEX")]
+    pub exclude: Option<String>,
+    /// only select these kinds of post: retweets, replies or originals. repeat or separate with commas.
+    /// the cutoff is optional in this mode.
+    #[arg(long, value_enum, value_name = "KIND", value_delimiter = ',')]
+    pub only: Vec<PostKind>,
+    /// never select these kinds of post, e.g. `--keep originals` to only purge retweets and replies.
+    #[arg(long, value_enum, value_name = "KIND", value_delimiter = ',')]
+    pub keep: Vec<PostKind>,
+    /// keep every post made on this month/day in any year (today when no value is given,
+    /// otherwise %m-%d or %Y-%m-%d). the cutoff is optional in this mode.
+    #[arg(long, value_name = "DATE", num_args = 0..=1, default_missing_value = "today")]
+    pub keep_anniversaries: Option<String>,
+    /// keep posts with at least this many likes.
+    #[arg(long, value_name = "N")]
+    pub min_favs: Option<u64>,
+    /// keep posts with at least this many retweets.
+    #[arg(long, value_name = "N")]
+    pub min_retweets: Option<u64>,
+    /// keep every reply chain that replies to or mentions any of these accounts, e.g. @friend.
+    /// repeat or separate with commas.
+    #[arg(long, value_name = "USER", value_delimiter = ',')]
+    pub keep_conversations_with: Vec<String>,
+    /// age a post must reach before it is eligible, scaled by engagement, e.g. "likes*30d + 180d".
+    /// units are s, min, h, d, w, m (30 days) and y. the cutoff is optional in this mode.
+    #[arg(long, value_name = "EXPRESSION")]
+    pub decay: Option<String>,
+    /// only select posts with their own expiry, from a hashtag such as #exp30d, once it has passed.
+    /// the cutoff is optional in this mode.
+    #[arg(long)]
+    pub expiry: bool,
+    /// JSON object mapping post ids to an expiry ("30d" after creation, or a date). implies --expiry.
+    #[arg(long, value_name = "FILE")]
+    pub expiry_file: Option<PathBuf>,
+    /// also fetch the posts made after the archive was generated from the API, so the selection covers them.
+    #[arg(long)]
+    pub supplement_api: bool,
+    /// accept a cutoff in the future, which selects every post of the account.
+    #[arg(long)]
+    pub force: bool,
+}
+
+impl Selection {
+    /// --before, or the positional cutoff it replaced.
+    pub fn cutoff(&self) -> Option<&str> {
+        self.before.as_deref().or(self.time.as_deref())
+    }
+}
+
+// how deletions are executed. shared by the default run and `apply`.
+#[derive(Args, Clone)]
+pub struct RunOptions {
+    /// accept every confirmation prompt.
+    #[arg(short, long)]
+    pub yes: bool,
+    /// never prompt. fails when a confirmation would be required and --yes isn't given.
+    #[arg(long)]
+    pub non_interactive: bool,
+    /// serve a page on this loopback address (127.0.0.1:8787 when no value is given) listing the
+    /// candidates, where posts can be marked to keep and the run started and paused. the run waits for
+    /// start instead of asking for confirmation. kept posts leave the remaining file undeleted. open the printed
+    /// url, which carries the token the page needs to change anything.
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = "127.0.0.1:8787")]
+    pub web_ui: Option<std::net::SocketAddr>,
+    /// list the posts that would be deleted with their date and text, and stop. nothing is sent or written.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// run against a simulated API and clock: rate limits, waits and expiries play out in seconds,
+    /// nothing is deleted and no file is written.
+    #[arg(long)]
+    pub simulate: bool,
+    /// with --simulate, answer the requests with the responses of a --record session instead, in
+    /// order and from the time it was recorded, to reproduce what a reported run ran into.
+    #[arg(long, value_name = "PATH", requires = "simulate")]
+    pub replay: Option<PathBuf>,
+    /// keep running with --expiry: posts that haven't expired yet are kept as candidates and each is
+    /// deleted once its own expiry passes.
+    #[arg(long)]
+    pub daemon: bool,
+    /// process candidates in batches, printing a summary and checkpointing after each one.
+    #[arg(long, value_enum, conflicts_with = "daemon")]
+    pub batch_by: Option<BatchBy>,
+    /// maximum requests per second (e.g. 0.2), for API keys shared with other applications.
+    #[arg(long, value_parser = parse_qps)]
+    pub max_qps: Option<f64>,
+    /// keep roughly this much (e.g. 512M) of the candidates in memory for the loop and the backups,
+    /// and spill the rest to temporary files. the remaining file still holds every candidate.
+    #[arg(long, value_parser = parse_size)]
+    pub max_memory: Option<u64>,
+    /// how many posts are deleted at once. a rate limit any of them runs into pauses all of them.
+    #[arg(long, default_value_t = 1)]
+    pub concurrency: usize,
+    /// share of the last 20 requests that may fail before --concurrency is halved, and once it's
+    /// down to 1, the pause between posts doubled. both come back once failures drop below half of this.
+    #[arg(long, default_value_t = 0.25, value_parser = parse_rate)]
+    pub slowdown_error_rate: f64,
+    /// how long to wait before trying a post again that failed transiently (409, 423 or 5xx),
+    /// e.g. while the platform reprocesses its media.
+    #[arg(long, default_value = "10min", value_parser = parse_cooling_off)]
+    pub retry_after: chrono::Duration,
+    /// how often such a post is tried again before it counts as failed. 0 fails it right away.
+    #[arg(long, default_value_t = 3)]
+    pub retry_attempts: u32,
+    /// longest single rate-limit wait (e.g. 24h, 90min). longer waits are capped and the request retried.
+    #[arg(long, default_value = "24h", value_parser = parse_wait)]
+    pub max_wait: std::time::Duration,
+    /// which API version deletes the posts: v1.1 or v2. oauth2 always uses v2.
+    #[arg(long, default_value = ApiVersion::DEFAULT, value_parser = parse_api_version)]
+    pub api_version: ApiVersion,
+    /// how to authorize the requests: oauth1 or oauth2.
+    #[arg(long, default_value = "oauth1", value_parser = parse_auth)]
+    pub auth: AuthMethod,
+    /// on a rejected OAuth 1.0a request, print the signature base string and parameters (keys cut
+    /// to a prefix, secrets never shown), or append them to FILE.
+    #[arg(long, value_name = "FILE", num_args = 0..=1)]
+    pub debug_auth: Option<Option<PathBuf>>,
+    /// token file written by `auth --oauth2`.
+    #[cfg(feature = "x-v2")]
+    #[arg(long, default_value = "oauth2-token.json")]
+    pub oauth2_token_file: PathBuf,
+    /// append one JSON line per processed post to this file.
+    #[arg(long)]
+    pub ledger: Option<PathBuf>,
+    /// append one JSON line per attempted deletion to this file: id, time, the start of the text,
+    /// the HTTP status and the outcome. every try of a retried post counts, a 429 too, and each
+    /// line is synced to disk before the run goes on. rotated like the ledger.
+    #[arg(long, value_name = "PATH")]
+    pub audit_log: Option<PathBuf>,
+    /// write a hash and the length of each post's text instead of the text to the ledger, the audit
+    /// log and the --dry-run listing, for artifacts handed to third parties. the hash is SHA-256 of the archived text.
+    #[arg(long)]
+    pub redact_text: bool,
+    /// rotate the ledger once it grows past this size (e.g. 10M).
+    #[arg(long, value_parser = parse_size)]
+    pub rotate_size: Option<u64>,
+    /// number of rotated files to keep.
+    #[arg(long, default_value_t = 5)]
+    pub rotate_keep: usize,
+    /// keep a small JSON file with processed/remaining/failed counts and an ETA up to date.
+    #[arg(long)]
+    pub progress_file: Option<PathBuf>,
+    /// on a terminal, show a bar with processed/total, the rate, the time spent waiting on rate
+    /// limits and the estimated completion time. the line per post still scrolls above it.
+    #[arg(long)]
+    pub progress_bar: bool,
+    /// also send run events as JSON to `console` (stderr), `file:<path>` (appended, one per line),
+    /// `webhook:<url>` (POSTed one at a time), `journald` (with POST_REMOVE_* fields) or `syslog`.
+    /// the last two suit --daemon, whose events then land in the host's log retention. repeatable,
+    /// or comma-separated.
+    #[arg(long = "sink", value_name = "SINK", value_delimiter = ',')]
+    pub sinks: Vec<SinkSpec>,
+    /// write the delete requests of this run and their responses to this file as HAR, for bug
+    /// reports. credentials and the bodies of successful responses are left out.
+    #[arg(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+    /// also write the summary printed at the end of the run to this file as JSON, for scripts.
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+    /// list what has to be done by hand when the run ends: Community posts and posts of permission
+    /// errors the API won't delete, and posts kept because their backup failed, each with its url
+    /// and what to do. JSON when the path ends in .json, a Markdown checklist otherwise.
+    #[arg(long, value_name = "PATH")]
+    pub manual_actions: Option<PathBuf>,
+    /// back up every post and its media here before deleting it. a post whose backup fails is kept.
+    #[arg(long)]
+    pub backup_dir: Option<PathBuf>,
+    /// how many posts are backed up at once, ahead of the deletions.
+    #[arg(long, default_value_t = 4)]
+    pub backup_concurrency: usize,
+    /// how many posts the backups may run ahead of the deletions.
+    #[arg(long, default_value_t = 16)]
+    pub backup_queue: usize,
+    /// also copy backed up media to `dir:<path>` or an S3-compatible `s3:<bucket-url>`
+    /// (credentials from AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY/AWS_REGION).
+    #[arg(long, value_name = "TARGET", requires = "backup_dir")]
+    pub rehost: Option<String>,
+    /// public url the rehosted media is reachable under. required for dir: targets.
+    #[arg(long, value_name = "URL")]
+    pub rehost_base_url: Option<String>,
+    /// keep the remaining file, ledger, progress file and backups of this run together in one directory.
+    /// without a value a new runs/<date> directory is created. explicit --ledger etc. still win.
+    #[arg(long, value_name = "DIR", num_args = 0..=1)]
+    pub workdir: Option<Option<PathBuf>>,
+    /// continue from the state file a previous run left next to the input (`<input>.state.json`),
+    /// or in --workdir DIR. without it a run refuses to start while that file exists.
+    #[arg(long)]
+    pub resume: bool,
+    /// also rewrite the remaining file every N processed posts. the journal keeps deletions in any
+    /// case; this keeps failures and the counts too, for runs that may be killed outright.
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub checkpoint_every: Option<usize>,
+    /// the zone dates are shown in next to UTC, in the --dry-run listing and the web ui: local,
+    /// an IANA name such as Asia/Tokyo, or an offset such as +09:00.
+    #[arg(long, default_value = "local")]
+    pub timezone: Zone,
+}
+
+/// the run flags on their own, for their defaults.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct RunArgs {
+    #[command(flatten)]
+    run: RunOptions,
+}
+
+/// what a run does with no flag given.
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunArgs::try_parse_from(std::iter::empty::<std::ffi::OsString>()).expect("every run flag has a default.").run
+    }
+}
+
+impl RunOptions {
+    /// resolves --workdir: creates the directory and points every unset output into it.
+    /// returns the directory, where the remaining file goes as well.
+    pub fn apply_workdir(&mut self) -> anyhow::Result<Option<PathBuf>> {
+        let Some(workdir) = &self.workdir else {
+            return Ok(None);
+        };
+        let dir = match workdir {
+            Some(dir) => dir.clone(),
+            None => {
+                let base = PathBuf::from("runs").join(chrono::Local::now().format("%Y-%m-%d").to_string());
+                // a second run on the same day gets runs/<date>-2 and so on.
+                (1..).map(|n| if n == 1 { base.clone() } else { PathBuf::from(format!("{}-{}", base.display(), n)) })
+                    .find(|dir| !dir.exists())
+                    .expect("unbounded range.")
+            },
+        };
+        std::fs::create_dir_all(&dir).map_err(|err| anyhow::anyhow!("failed to create {}. err={}", dir.display(), err))?;
+        self.ledger.get_or_insert_with(|| dir.join("ledger.jsonl"));
+        self.progress_file.get_or_insert_with(|| dir.join("progress.json"));
+        self.backup_dir.get_or_insert_with(|| dir.join("backup"));
+        println!("workdir. path={}", dir.display());
+        Ok(Some(dir))
+    }
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommand {
+    /// check that every deleted post of a ledger has an intact backup.
+    Verify {
+        /// ledger written by --ledger.
+        #[arg(long)]
+        ledger: PathBuf,
+        /// directory given to --backup-dir.
+        #[arg(long)]
+        backup_dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RestoreCommand {
+    /// search the backups and write a plan to post the matching posts again, threads in order.
+    Plan {
+        /// text the posts contain. compared NFC-normalized and case folded.
+        #[arg(long = "match", value_name = "TEXT")]
+        pattern: String,
+        /// directory given to --backup-dir.
+        #[arg(long)]
+        backup_dir: PathBuf,
+        /// repost plan to write.
+        #[arg(long, default_value = "restore.json")]
+        out: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// one Markdown file per post with front-matter, e.g. into an Obsidian vault.
+    Markdown {
+        /// directory given to --backup-dir.
+        #[arg(long)]
+        backup_dir: PathBuf,
+        /// directory to write the Markdown files into.
+        #[arg(long)]
+        out: PathBuf,
+        /// the zone dates are shown in: local, an IANA name such as Asia/Tokyo, or an offset such as +09:00.
+        #[arg(long, default_value = "local")]
+        timezone: Zone,
+    },
+    /// one Hugo page bundle per thread under <site>/content/posts, self-replies merged.
+    Hugo {
+        /// directory given to --backup-dir.
+        #[arg(long)]
+        backup_dir: PathBuf,
+        /// root of the Hugo site.
+        #[arg(long)]
+        out: PathBuf,
+        /// the zone dates are shown in: local, an IANA name such as Asia/Tokyo, or an offset such as +09:00.
+        #[arg(long, default_value = "local")]
+        timezone: Zone,
+    },
+    /// one Jekyll post per thread under <site>/_posts, self-replies merged. media goes to assets/posts.
+    Jekyll {
+        /// directory given to --backup-dir.
+        #[arg(long)]
+        backup_dir: PathBuf,
+        /// root of the Jekyll site.
+        #[arg(long)]
+        out: PathBuf,
+        /// the zone dates are shown in: local, an IANA name such as Asia/Tokyo, or an offset such as +09:00.
+        #[arg(long, default_value = "local")]
+        timezone: Zone,
+    },
+    /// every archive entry as queryable posts/entities/media tables.
+    Sqlite {
+        /// database file to create.
+        db: PathBuf,
+        /// path to tweets.json.
+        #[arg(long)]
+        archive: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// authorize the app for an account with a PIN and store ACCESS_KEY/ACCESS_SECRET.
+    /// needs CONSUMER_KEY and CONSUMER_SECRET.
+    Auth {
+        /// dotenv file to write the access token to. other lines are kept.
+        #[arg(long, default_value = ".env")]
+        env_file: PathBuf,
+        /// authorize with OAuth 2.0 PKCE instead and write a token file for --auth oauth2.
+        /// needs OAUTH2_CLIENT_ID, and OAUTH2_CLIENT_SECRET for confidential clients.
+        #[cfg_attr(feature = "x-v2", arg(long, requires = "redirect_uri"))]
+        #[cfg_attr(not(feature = "x-v2"), arg(long))]
+        oauth2: bool,
+        /// callback url registered for the app.
+        #[cfg(feature = "x-v2")]
+        #[arg(long, value_name = "URL", requires = "oauth2")]
+        redirect_uri: Option<String>,
+        /// where --oauth2 writes the token.
+        #[cfg(feature = "x-v2")]
+        #[arg(long, default_value = "oauth2-token.json")]
+        token_file: PathBuf,
+    },
+    /// check the API connection, credentials, archive, backup disk space and clock in one go.
+    Doctor {
+        /// the input to check, in any form a run takes: tweets.json or tweets.js, an archive
+        /// directory, a glob over its parts, the downloaded zip or a remaining file.
+        #[arg(long)]
+        archive: Option<PathBuf>,
+        /// directory that will be given to --backup-dir.
+        #[arg(long)]
+        backup_dir: Option<PathBuf>,
+    },
+    /// summarize what a past run did from its ledger, without the original archive.
+    Report {
+        /// ledger written by --ledger.
+        #[arg(long)]
+        from_ledger: PathBuf,
+        /// how many of the most engaged deleted posts to list.
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// show a hash and the length of each post's text instead of the text.
+        #[arg(long)]
+        redact_text: bool,
+        /// the zone dates are shown in: local, an IANA name such as Asia/Tokyo, or an offset such as +09:00.
+        #[arg(long, default_value = "local")]
+        timezone: Zone,
+    },
+    /// inspect backups written by --backup-dir.
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
+    /// bring back deleted posts from their backups.
+    Restore {
+        #[command(subcommand)]
+        command: RestoreCommand,
+    },
+    /// convert backed up posts into other formats.
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    /// write the candidates to a plan file instead of deleting them.
+    Plan {
+        #[command(flatten)]
+        selection: Box<Selection>,
+        /// plan file to write. with --split-by-quota, numbered files next to it (plan-001.json, ...).
+        #[arg(long, default_value = "plan.json")]
+        out: PathBuf,
+        /// split the plan into chunks of --daily-limit posts, one per day.
+        #[arg(long, requires = "daily_limit")]
+        split_by_quota: bool,
+        /// how many deletions the API allows per day.
+        #[arg(long)]
+        daily_limit: Option<usize>,
+        /// list the posts added to or removed from the selection since this earlier plan.
+        #[arg(long, value_name = "PLAN")]
+        compare: Option<PathBuf>,
+        /// also write the skip reason of every other post as CSV. the plan itself lists them under "skipped".
+        #[arg(long, value_name = "FILE")]
+        skipped_csv: Option<PathBuf>,
+        /// mark the plan so `apply` refuses it until someone runs `approve` on it.
+        #[arg(long)]
+        require_approval: bool,
+    },
+    /// delete every post of the account regardless of date. arms on the first run after two
+    /// confirmations, deletes on a run after the cooling-off. --backup-dir is required.
+    Nuke {
+        /// path to tweets.json.
+        tweets: PathBuf,
+        /// how long to wait between arming and deleting (e.g. 24h, 7d).
+        #[arg(long, default_value = "24h", value_parser = parse_cooling_off)]
+        cooling_off: chrono::Duration,
+        /// accept the typed "delete everything" confirmations when there is no terminal. --yes doesn't.
+        #[arg(long)]
+        yes_delete_everything: bool,
+        #[command(flatten)]
+        run: RunOptions,
+    },
+    /// remove every like of like.js through the unfavorite endpoint. the file is rewritten with whatever is left.
+    Likes {
+        /// path to like.js, or the archive directory.
+        likes: PathBuf,
+        #[command(flatten)]
+        run: RunOptions,
+    },
+    /// delete the direct messages of direct-messages.js created before a date, conversation by conversation.
+    /// the file is rewritten with whatever is left.
+    Dm {
+        /// path to direct-messages.js, or the archive directory.
+        messages: PathBuf,
+        /// delete messages created before this instant. %Y-%m-%d is midnight UTC, RFC 3339 is exact.
+        #[arg(long, value_name = "DATE")]
+        before: String,
+        #[command(flatten)]
+        run: RunOptions,
+    },
+    /// remove the bookmarks of posts created before a date. needs --auth oauth2. --dry-run lists them.
+    Bookmarks {
+        /// remove bookmarks of posts created before this instant. %Y-%m-%d is midnight UTC, RFC 3339 is exact.
+        #[arg(long, value_name = "DATE")]
+        before: String,
+        /// where the listed bookmarks are kept between runs. rewritten with whatever is left, and
+        /// resumed from instead of listing again while it exists.
+        #[arg(long, value_name = "FILE", default_value = "bookmarks.json")]
+        state: PathBuf,
+        #[command(flatten)]
+        run: RunOptions,
+    },
+    /// delete the posts of a plan file. the plan is rewritten with whatever is left.
+    Apply {
+        plan: PathBuf,
+        /// approvals file written by `approve`. the plan only runs if an approval covers it.
+        /// required for plans written with --require-approval.
+        #[arg(long, value_name = "FILE")]
+        approvals: Option<PathBuf>,
+        #[command(flatten)]
+        run: RunOptions,
+    },
+    /// approve a plan for `apply`, as the second person of a change-control process.
+    Approve {
+        plan: PathBuf,
+        /// who approves, recorded with the approval.
+        #[arg(long)]
+        approver: String,
+        /// plan_hash printed when the plan was written. refuses a plan that has changed since.
+        #[arg(long, value_name = "SHA256")]
+        hash: Option<String>,
+        /// approvals file to append to.
+        #[arg(long, value_name = "FILE", default_value = "approvals.jsonl")]
+        approvals: PathBuf,
+    },
+}
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub selection: Selection,
+    #[command(flatten)]
+    pub run: RunOptions,
+    /// install a systemd user service and timer that run the current command line.
+    #[arg(long)]
+    pub install_service: bool,
+    /// print the generated systemd units instead of installing them.
+    #[arg(long)]
+    pub print_service: bool,
+    /// OnCalendar expression for the generated timer.
+    #[arg(long, default_value = "daily")]
+    pub schedule: String,
+    /// where to install the units. defaults to ~/.config/systemd/user.
+    #[arg(long)]
+    pub service_dir: Option<PathBuf>,
+    /// append structured JSON log lines to this file: every request with its post id, status code
+    /// and any wait or retry with its reason, besides what's printed. rotated by --rotate-size.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// read options and credentials from this TOML file instead of ./post_remove.toml. flags and
+    /// environment variables (POST_REMOVE_<OPTION>, CONSUMER_KEY, ...) take precedence over it,
+    /// and it over .env.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// use the `[profiles.NAME]` table of the config, with its own credentials and defaults, over
+    /// the rest of the file. for running the tool against several accounts.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// with --version, also print the commit, build date, target and features of the build.
+    #[arg(long, global = true)]
+    pub verbose: bool,
+    /// color the warnings on stderr.
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+    /// the least severe events written to --log-file: error, warn, info, debug or trace.
+    #[arg(long, global = true, default_value = "info", requires = "log_file")]
+    pub log_level: tracing::level_filters::LevelFilter,
+}
+
+/// parses sizes such as `512`, `64K`, `10M` or `1G`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, ""),
+    };
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => return Err(format!("unknown size unit. value={}", value)),
+    };
+    let number = number.parse::<u64>().map_err(|_| format!("expect a size like 10M. value={}", value))?;
+    number.checked_mul(multiplier).ok_or_else(|| format!("size too large. value={}", value))
+}
+
+fn parse_wait(value: &str) -> Result<std::time::Duration, String> {
+    crate::duration::parse(value)
+        .and_then(|duration| duration.to_std().ok())
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| format!("expect a duration like 24h. value={}", value))
+}
+
+fn parse_cooling_off(value: &str) -> Result<chrono::Duration, String> {
+    crate::duration::parse(value).ok_or_else(|| format!("expect a duration like 24h. value={}", value))
+}
+
+fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("expect a number between 0 and 1. value={}", value)),
+    }
+}
+
+fn parse_qps(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(qps) if qps > 0.0 && qps.is_finite() => Ok(qps),
+        _ => Err(format!("expect a positive number. value={}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_take_binary_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("64K"), Ok(64 << 10));
+        assert_eq!(parse_size("10mb"), Ok(10 << 20));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert!(parse_size("10T").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("99999999999G").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use std::{future::Future, pin::Pin, sync::{Arc, Mutex}, time::Duration};
+
+pub type Sleep<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// where the run gets "now" and how it waits, so a simulation can skip the waiting.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+    fn sleep(&self, duration: Duration) -> Sleep<'_>;
+    /// simulated clocks jump instead of waiting, so loops that poll in small steps can take one big step.
+    fn is_simulated(&self) -> bool {
+        false
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep<'_> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// starts at the real time and only moves when something sleeps.
+pub struct SimulatedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl SimulatedClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(start) }
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned.")
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep<'_> {
+        let step = chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX);
+        {
+            let mut now = self.now.lock().expect("clock lock poisoned.");
+            *now = now.checked_add_signed(step).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        }
+        Box::pin(tokio::task::yield_now())
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+}
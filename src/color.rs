@@ -0,0 +1,28 @@
+use clap::ValueEnum;
+use console::StyledObject;
+use std::env;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ColorChoice {
+    /// when stderr is a terminal and NO_COLOR isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+/// decides once per process whether warnings are colored. on Windows, asking the console turns
+/// on its ANSI support, which older consoles would otherwise print as escape codes.
+pub fn init(choice: ColorChoice) {
+    let terminal = console::colors_enabled_stderr();
+    let enabled = match choice {
+        ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    console::set_colors_enabled_stderr(enabled);
+}
+
+/// the `WARNING:` that starts a warning on stderr, in yellow when colors are on.
+pub fn warning() -> StyledObject<&'static str> {
+    console::style("WARNING:").yellow().for_stderr()
+}
@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use clap::Command;
+use std::{collections::BTreeMap, env, ffi::OsString, fs, path::PathBuf};
+
+/// read from the working directory when --config isn't given.
+pub const FILE: &str = "post_remove.toml";
+
+/// the environment variable every option can also be set with, e.g. POST_REMOVE_MAX_QPS.
+fn env_name(id: &str) -> String {
+    format!("POST_REMOVE_{}", id.to_ascii_uppercase().replace('-', "_"))
+}
+
+/// gives every option of `command` and its subcommands a POST_REMOVE_* environment variable,
+/// which is how the config file reaches them. a flag on the command line still wins.
+pub fn with_env(command: Command) -> Command {
+    let command = command.mut_args(|arg| {
+        let id = arg.get_id().as_str();
+        if id == "help" || id == "version" || arg.get_env().is_some() {
+            return arg;
+        }
+        // --help lists the variable, never what the config put there.
+        let name = env_name(id);
+        arg.env(name).hide_env_values(true)
+    });
+    let names: Vec<String> = command.get_subcommands().map(|subcommand| subcommand.get_name().to_string()).collect();
+    names.into_iter().fold(command, |command, name| command.mut_subcommand(name, with_env))
+}
+
+/// an option the config can set: its id, and whether it takes comma-separated values.
+type Known = BTreeMap<String, (String, bool)>;
+
+/// the options of `command` and its subcommands, by id and by flag name.
+fn ids(command: &Command, into: &mut Known) {
+    for arg in command.get_arguments() {
+        let option = (arg.get_id().as_str().to_string(), arg.get_value_delimiter() == Some(','));
+        if let Some(long) = arg.get_long() {
+            into.insert(long.replace('-', "_"), option.clone());
+        }
+        into.insert(option.0.clone(), option);
+    }
+    for subcommand in command.get_subcommands() {
+        ids(subcommand, into);
+    }
+}
+
+/// the value of `flag` in the raw arguments, since the file has to be read before they're parsed.
+fn flag(args: &[OsString], flag: &str) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--" {
+            break;
+        }
+        if arg == flag {
+            return args.next().map(|value| value.to_string_lossy().into_owned());
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// reads the config file into the environment, without replacing what's already set there.
+/// `[credentials]` holds CONSUMER_KEY and the other variables of .env, in lower case. every other
+/// key, in any table, is an option by its flag name (`max_qps` or `max-qps`); tables like `[run]`
+/// only group them. lists only go to options that take comma-separated values. with --profile,
+/// `[profiles.NAME]` is read the same way and wins over the rest of the file.
+pub fn load(command: &Command, args: &[OsString]) -> Result<()> {
+    let profile = flag(args, "--profile").or_else(|| env::var(env_name("profile")).ok());
+    let path = match flag(args, "--config").map(PathBuf::from).or_else(|| env::var_os(env_name("config")).map(PathBuf::from)) {
+        Some(path) => path,
+        None if fs::metadata(FILE).is_ok() => PathBuf::from(FILE),
+        None => {
+            anyhow::ensure!(profile.is_none(), "--profile needs a config file. expected={}", FILE);
+            return Ok(());
+        },
+    };
+    let text = fs::read_to_string(&path).with_context(|| format!("failed to read config. path={}", path.display()))?;
+    let mut table: toml::Table = text.parse().with_context(|| format!("failed to parse config. path={}", path.display()))?;
+    let found = match table.remove("schema_version") {
+        Some(version) => version.as_integer().and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("schema_version isn't a number. path={}", path.display()))?,
+        None => crate::schema::CONFIG,
+    };
+    crate::schema::ensure_supported("config", found, crate::schema::CONFIG)?;
+    let mut known = BTreeMap::new();
+    ids(command, &mut known);
+    let mut profiles = match table.remove("profiles") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => anyhow::bail!("profiles must be a table of tables. path={}", path.display()),
+        None => toml::Table::new(),
+    };
+    let mut values = BTreeMap::new();
+    collect(table, &known, &mut values)?;
+    if let Some(profile) = &profile {
+        let names = profiles.keys().cloned().collect::<Vec<_>>().join(",");
+        match profiles.remove(profile) {
+            Some(toml::Value::Table(table)) => collect(table, &known, &mut values)?,
+            Some(_) => anyhow::bail!("profile must be a table. profile={}", profile),
+            None => anyhow::bail!("unknown profile. profile={} profiles={}", profile, names),
+        }
+    }
+    let count = values.len();
+    for (name, value) in values {
+        if env::var_os(&name).is_none() {
+            env::set_var(name, value);
+        }
+    }
+    match profile {
+        Some(profile) => println!("loaded config. path={} profile={} keys={}", path.display(), profile, count),
+        None => println!("loaded config. path={} keys={}", path.display(), count),
+    }
+    Ok(())
+}
+
+/// the variables `table` sets, by name. a later table replaces what an earlier one set.
+fn collect(table: toml::Table, known: &Known, into: &mut BTreeMap<String, String>) -> Result<()> {
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("credentials", toml::Value::Table(credentials)) => {
+                for (key, value) in credentials {
+                    into.insert(key.to_ascii_uppercase(), scalar(&key, value)?);
+                }
+            },
+            (_, toml::Value::Table(group)) => {
+                for (key, value) in group {
+                    into.insert(option(&key, &value, known)?, scalar(&key, value)?);
+                }
+            },
+            (_, value) => {
+                into.insert(option(&key, &value, known)?, scalar(&key, value)?);
+            },
+        }
+    }
+    Ok(())
+}
+
+/// the variable of the option `key` names. a list is joined with commas, so it only goes to an
+/// option that splits them again.
+fn option(key: &str, value: &toml::Value, known: &Known) -> Result<String> {
+    let (id, takes_list) = known.get(&key.replace('-', "_")).with_context(|| format!("unknown key in config. key={}", key))?;
+    anyhow::ensure!(*takes_list || !value.is_array(), "option takes one value, not a list. key={}", key);
+    Ok(env_name(id))
+}
+
+fn scalar(key: &str, value: toml::Value) -> Result<String> {
+    Ok(match value {
+        toml::Value::String(value) => value,
+        toml::Value::Integer(value) => value.to_string(),
+        toml::Value::Float(value) => value.to_string(),
+        toml::Value::Boolean(value) => value.to_string(),
+        toml::Value::Datetime(value) => value.to_string(),
+        toml::Value::Array(values) => values.into_iter().map(|value| scalar(key, value)).collect::<Result<Vec<_>>>()?.join(","),
+        toml::Value::Table(_) => anyhow::bail!("tables don't nest in config. key={}", key),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+
+    fn collected(text: &str) -> Result<BTreeMap<String, String>> {
+        let mut known = BTreeMap::new();
+        ids(&Cli::command(), &mut known);
+        let mut values = BTreeMap::new();
+        collect(text.parse()?, &known, &mut values)?;
+        Ok(values)
+    }
+
+    #[test]
+    fn keys_become_option_variables() {
+        let values = collected(r#"
+            max-qps = 2
+            [run]
+            max_wait = "10m"
+            sink = ["console", "file:events.jsonl"]
+            [credentials]
+            consumer_key = "key"
+        "#).unwrap();
+        assert_eq!(values["POST_REMOVE_MAX_QPS"], "2");
+        assert_eq!(values["POST_REMOVE_MAX_WAIT"], "10m");
+        assert_eq!(values["POST_REMOVE_SINKS"], "console,file:events.jsonl");
+        assert_eq!(values["CONSUMER_KEY"], "key");
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_lists_for_single_values() {
+        assert!(collected("max_qs = 2").is_err());
+        assert!(collected("max_qps = [1, 2]").is_err());
+        assert!(collected("[run.nested]\nmax_qps = 2").is_err());
+    }
+
+    #[test]
+    fn list_variables_split_again() {
+        let matches = with_env(Cli::command()).try_get_matches_from(["post_remove", "--before", "2024-01-01", "--sink", "console,file:events.jsonl", "tweets.json"]).unwrap();
+        assert_eq!(matches.get_many::<crate::events::SinkSpec>("sinks").unwrap().count(), 2);
+    }
+}
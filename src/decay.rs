@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use chrono::Duration;
+use serde_json::Value;
+
+use crate::duration;
+
+#[derive(Clone, Copy)]
+enum Metric {
+    Likes,
+    Retweets,
+    Engagement,
+}
+
+impl Metric {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "likes" | "favs" => Some(Metric::Likes),
+            "retweets" | "rts" => Some(Metric::Retweets),
+            "engagement" => Some(Metric::Engagement),
+            _ => None,
+        }
+    }
+
+    fn value(&self, tweet: &Value) -> i64 {
+        let count = |key: &str| tweet["tweet"][key].as_str().and_then(|count| count.parse::<i64>().ok()).unwrap_or(0);
+        match self {
+            Metric::Likes => count("favorite_count"),
+            Metric::Retweets => count("retweet_count"),
+            Metric::Engagement => count("favorite_count") + count("retweet_count"),
+        }
+    }
+}
+
+/// an age policy such as `likes*30d + 180d`: a post becomes eligible once it is older than the sum.
+pub struct Decay {
+    terms: Vec<(Option<Metric>, Duration)>,
+}
+
+impl Decay {
+    /// terms are joined by `+`. each is a duration, optionally multiplied by likes, retweets or engagement.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        for term in expression.split('+') {
+            let factors: Vec<&str> = term.split('*').map(str::trim).collect();
+            let parsed = match factors[..] {
+                [duration] => duration::parse(duration).map(|duration| (None, duration)),
+                [left, right] => match (Metric::parse(left), Metric::parse(right)) {
+                    (Some(metric), None) => duration::parse(right).map(|duration| (Some(metric), duration)),
+                    (None, Some(metric)) => duration::parse(left).map(|duration| (Some(metric), duration)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            terms.push(parsed.with_context(|| format!("invalid --decay term. expect e.g. likes*30d or 180d. term={}", term.trim()))?);
+        }
+        Ok(Self { terms })
+    }
+
+    /// terms that add nothing to the allowed age.
+    pub fn lint(&self) -> Vec<String> {
+        self.terms.iter()
+            .filter(|(_, duration)| duration.is_zero())
+            .map(|(metric, _)| match metric {
+                Some(_) => "a --decay term multiplies by zero and never extends the allowed age.".to_string(),
+                None => "a --decay term of zero has no effect.".to_string(),
+            })
+            .collect()
+    }
+
+    pub fn allowed_age(&self, tweet: &Value) -> Duration {
+        self.terms.iter().fold(Duration::zero(), |total, (metric, duration)| {
+            let term = match metric {
+                Some(metric) => Duration::try_seconds(duration.num_seconds().saturating_mul(metric.value(tweet))).unwrap_or(Duration::MAX),
+                None => *duration,
+            };
+            total.checked_add(&term).unwrap_or(Duration::MAX)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn terms_add_up() {
+        let decay = Decay::parse("likes*30d + 180d").unwrap();
+        let tweet = json!({ "tweet": { "favorite_count": "2", "retweet_count": "5" } });
+        assert_eq!(decay.allowed_age(&tweet), Duration::days(240));
+        // the metric may come on either side.
+        let decay = Decay::parse("1d*engagement").unwrap();
+        assert_eq!(decay.allowed_age(&tweet), Duration::days(7));
+    }
+
+    #[test]
+    fn rejects_unknown_terms() {
+        assert!(Decay::parse("views*1d").is_err());
+        assert!(Decay::parse("likes*retweets").is_err());
+        assert!(Decay::parse("likes*1d*2").is_err());
+        assert!(Decay::parse("30d +").is_err());
+    }
+
+    #[test]
+    fn lints_zero_terms() {
+        assert_eq!(Decay::parse("likes*0d + 30d").unwrap().lint().len(), 1);
+        assert!(Decay::parse("likes*1d").unwrap().lint().is_empty());
+    }
+}
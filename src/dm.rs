@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::{cli::{BatchBy, RunOptions}, filter::Filter, get_file_input, new_clock, new_metadata, parse_instant, resume_state, engine::run};
+
+/// direct-messages.js itself, or the one in an archive directory.
+fn messages_js(path: &Path) -> PathBuf {
+    if !path.is_dir() {
+        return path.to_path_buf();
+    }
+    let data = path.join("data").join("direct-messages.js");
+    if data.exists() {
+        data
+    } else {
+        path.join("direct-messages.js")
+    }
+}
+
+/// one entry per message, with the `tweet` object a run works on. `dm` keeps the conversation and
+/// the message as the archive has them, and marks the entry as a message.
+fn message_entries(entry: Value) -> Vec<Value> {
+    if entry["tweet"].is_object() {
+        // already flattened, by the run that wrote this remaining file.
+        return vec![entry];
+    }
+    let conversation = &entry["dmConversation"];
+    let conversation_id = conversation["conversationId"].as_str().unwrap_or_default();
+    conversation["messages"].as_array().into_iter().flatten()
+        .filter_map(|message| {
+            let message = &message["messageCreate"];
+            let id = message["id"].as_str()?;
+            // the format of tweets.js, so the date filters and batching read messages like posts.
+            let created_at = message["createdAt"].as_str()
+                .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+                .map(|created_at| created_at.to_utc().format("%a %b %d %H:%M:%S %z %Y").to_string());
+            Some(json!({
+                "dm": { "conversationId": conversation_id, "messageCreate": message },
+                "tweet": { "id": id, "id_str": id, "full_text": message["text"], "created_at": created_at },
+            }))
+        })
+        .collect()
+}
+
+/// deletes the messages of direct-messages.js created before `before`, one conversation at a time
+/// with a summary and a checkpoint after each. deleting only removes a message from this account's view.
+pub async fn delete(path: &Path, before: &str, options: &RunOptions) -> Result<()> {
+    anyhow::ensure!(options.backup_dir.is_none(), "--backup-dir doesn't apply to messages yet.");
+    let cutoff = parse_instant(before).with_context(|| format!("failed --before parse. (format %Y-%m-%d or RFC 3339) value={}", before))?;
+    let path = messages_js(path);
+    let (entries, previous, state_path) = resume_state(get_file_input(&path)?, options)?;
+    let messages: Vec<Value> = entries.into_iter().flat_map(message_entries).collect();
+    let total = messages.len();
+    let now = chrono::Utc::now();
+    let older = Filter::created_before(cutoff);
+    let candidates: Vec<Value> = messages.into_iter().filter(|message| older.matches(message, now)).collect();
+    println!("loaded messages. path={} messages={} candidates={}", path.display(), total, candidates.len());
+    let mut metadata = new_metadata(Some(before.to_string()), vec!["direct messages".to_string()], total, candidates.len());
+    metadata.resume(previous);
+    let mut options = options.clone();
+    options.batch_by = Some(options.batch_by.unwrap_or(BatchBy::Conversation));
+    run(candidates, state_path, metadata, &options, new_clock(&options)).await
+}
@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{fs, path::Path};
+
+use crate::{get_entries, post_time, thread, verify_credentials, Credentials};
+
+const API_HOST: &str = "https://api.x.com";
+/// OAuth 1.0a signatures are rejected once the clock is off by a few minutes.
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+/// free space below this is reported even without archive media to estimate from.
+const MIN_FREE_BYTES: u64 = 1 << 30;
+
+fn report(check: &str, result: Result<String>) -> usize {
+    match result {
+        Ok(detail) => {
+            println!("ok. check={} {}", check, detail);
+            0
+        },
+        Err(err) => {
+            println!("FAILED. check={} err={:#}", check, err);
+            1
+        },
+    }
+}
+
+/// any HTTP answer counts. returns the server's Date for the clock check.
+async fn connectivity() -> Result<(String, Option<DateTime<Utc>>)> {
+    let response = reqwest::Client::new().head(API_HOST).send().await.with_context(|| format!("failed to reach the API. host={}", API_HOST))?;
+    let date = response.headers().get("Date")
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .map(|date| date.to_utc());
+    Ok((format!("host={} status={}", API_HOST, response.status()), date))
+}
+
+async fn credentials() -> Result<String> {
+    let credentials = Credentials::from_env()?;
+    let account = verify_credentials(&credentials).await?;
+    let access_level = account.access_level.as_deref().unwrap_or("unknown");
+    anyhow::ensure!(access_level.contains("write") || access_level == "unknown",
+        "the app can't delete posts. give it read and write permission and regenerate the access token. access_level={}", access_level);
+    Ok(format!("id={} screen_name={} access_level={}", account.id, account.screen_name, access_level))
+}
+
+fn clock(server: Option<DateTime<Utc>>) -> Result<String> {
+    let server = server.context("the API didn't send a Date header.")?;
+    let skew = (Utc::now() - server).num_seconds();
+    anyhow::ensure!(skew.abs() <= MAX_CLOCK_SKEW_SECONDS, "the system clock is off. sync it (e.g. NTP). skew={}s", skew);
+    Ok(format!("skew={}s", skew))
+}
+
+/// loads `path` the way a run does, so whatever a run accepts passes here too, and then checks
+/// every post has what the selection reads.
+fn archive(path: &Path) -> Result<String> {
+    let entries = get_entries(path)?;
+    // likes of a resumed run have neither.
+    let posts: Vec<&Value> = entries.iter().filter(|entry| !entry["like"].is_object()).collect();
+    let unreadable = posts.iter().filter(|entry| thread::id(entry).is_none() || post_time(entry).is_none()).count();
+    anyhow::ensure!(unreadable == 0, "posts without a valid id or created_at. posts={} unreadable={}", posts.len(), unreadable);
+    Ok(format!("posts={} likes={}", posts.len(), entries.len() - posts.len()))
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir).into_iter().flatten().flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// the archive's own media is a good estimate of what backups will take.
+fn disk_space(backup_dir: &Path, archive: Option<&Path>) -> Result<String> {
+    // the directory may not exist yet. check the closest ancestor that does.
+    let existing = backup_dir.ancestors().find(|dir| dir.is_dir()).unwrap_or(Path::new("."));
+    let available = fs2::available_space(existing).with_context(|| format!("failed to read free space. path={}", existing.display()))?;
+    // next to tweets.js, or in data/ of an archive directory.
+    let dir = archive.and_then(|archive| if archive.is_dir() { Some(archive) } else { archive.parent() });
+    let needed = dir.map(|dir| dir_size(&dir.join("tweets_media")) + dir_size(&dir.join("data").join("tweets_media"))).unwrap_or(0).max(MIN_FREE_BYTES);
+    anyhow::ensure!(available >= needed, "not enough free space for backups. available={} needed={}", available, needed);
+    Ok(format!("path={} available={} needed={}", existing.display(), available, needed))
+}
+
+/// runs every check and prints one line per check. returns the number of failed checks.
+pub async fn run(archive_path: Option<&Path>, backup_dir: Option<&Path>) -> usize {
+    let mut problems = 0;
+    match connectivity().await {
+        Ok((detail, date)) => {
+            problems += report("connectivity", Ok(detail));
+            problems += report("clock", clock(date));
+            problems += report("credentials", credentials().await);
+        },
+        Err(err) => problems += report("connectivity", Err(err)),
+    }
+    if let Some(path) = archive_path {
+        problems += report("archive", archive(path));
+    }
+    if let Some(dir) = backup_dir {
+        problems += report("disk_space", disk_space(dir, archive_path));
+    }
+    println!("doctor done. problems={}", problems);
+    problems
+}
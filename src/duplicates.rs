@@ -0,0 +1,53 @@
+use clap::ValueEnum;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::text;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum KeepCopy {
+    Earliest,
+    MostEngaged,
+}
+
+/// text used to recognize the same post made repeatedly. t.co links differ for every post, so they're dropped.
+fn duplicate_key(full_text: &str, ignore_case: bool, strip: bool) -> String {
+    let words: Vec<&str> = full_text.split_whitespace()
+        .filter(|word| !word.starts_with("https://t.co/") && !word.starts_with("http://t.co/"))
+        .collect();
+    text::comparable(&words.join(" "), ignore_case, strip)
+}
+
+fn id(tweet: &Value) -> u64 {
+    tweet["tweet"]["id"].as_str().and_then(|id| id.parse().ok()).unwrap_or(u64::MAX)
+}
+
+fn engagement(tweet: &Value) -> u64 {
+    ["favorite_count", "retweet_count"].iter()
+        .filter_map(|key| tweet["tweet"][key].as_str().and_then(|count| count.parse::<u64>().ok()))
+        .sum()
+}
+
+/// every copy of a repeated post except the one to keep. posts without text are never duplicates.
+pub fn find(entries: &[Value], keep: KeepCopy, ignore_case: bool, strip: bool) -> Vec<Value> {
+    let mut groups: HashMap<String, Vec<&Value>> = HashMap::new();
+    for tweet in entries {
+        let key = duplicate_key(text::of(&tweet["tweet"]), ignore_case, strip);
+        if !key.is_empty() {
+            groups.entry(key).or_default().push(tweet);
+        }
+    }
+
+    let mut duplicates = Vec::new();
+    for (_, mut copies) in groups.into_iter().filter(|(_, copies)| copies.len() > 1) {
+        // snowflake ids grow over time, so the smallest id is the earliest copy.
+        match keep {
+            KeepCopy::Earliest => copies.sort_by_key(|tweet| id(tweet)),
+            KeepCopy::MostEngaged => copies.sort_by_key(|tweet| (std::cmp::Reverse(engagement(tweet)), id(tweet))),
+        }
+        duplicates.extend(copies.into_iter().skip(1).cloned());
+    }
+    // newest first, like the archive itself.
+    duplicates.sort_by_key(|tweet| std::cmp::Reverse(id(tweet)));
+    duplicates
+}
@@ -0,0 +1,43 @@
+use chrono::Duration;
+
+/// parses `90s`, `30min`, `12h`, `90d`, `2w`, `6m` (months of 30 days) or `1y` (365 days).
+pub fn parse(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let index = value.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = value.split_at(index);
+    let number: i64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => 1,
+        "min" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "m" => 30 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Duration::try_seconds(number.checked_mul(seconds)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units() {
+        assert_eq!(parse("90s"), Some(Duration::seconds(90)));
+        assert_eq!(parse("30min"), Some(Duration::minutes(30)));
+        assert_eq!(parse(" 12h "), Some(Duration::hours(12)));
+        assert_eq!(parse("2w"), Some(Duration::days(14)));
+        assert_eq!(parse("6m"), Some(Duration::days(180)));
+        assert_eq!(parse("1y"), Some(Duration::days(365)));
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_read() {
+        assert_eq!(parse("12"), None);
+        assert_eq!(parse("h"), None);
+        assert_eq!(parse("3 days"), None);
+        assert_eq!(parse("99999999999999999y"), None);
+    }
+}
@@ -0,0 +1,1152 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_util::{stream::FuturesOrdered, FutureExt, StreamExt};
+use reqwest::{header::HeaderMap, Response};
+use serde_json::{json, Value};
+use std::{collections::{BTreeMap, HashMap}, io::{self, IsTerminal, Write}, path::PathBuf, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, OnceLock}};
+use tokio::sync::mpsc;
+
+use crate::{
+    api::{Action, Api},
+    audit::AuditLog,
+    backup::{Backup, Pipeline},
+    bar::Bar,
+    cli::{AuthMethod, RunOptions},
+    clock::SharedClock,
+    confirm, events::{self, Event, Events}, expiry, format_remaining, get_entries, journal::{self, Journal},
+    ledger::{Ledger, LedgerRecord, Rotation},
+    make_batches, manual::ManualActions, new_clock, new_metadata, pace,
+    progress::ProgressFile,
+    quota::{DailyQuota, Window},
+    record::Recorder, rehost::Rehost, replay, report, retry, save_original,
+    schedule::Schedule, signing, spill::Spool, state_path_for,
+    status::{self, SharedStatus, Status},
+    summary::{Summary, Waits},
+    systemd, text, thread, verify_credentials, web, write_atomically, Account, Credentials, Error, RunMetadata,
+};
+#[cfg(feature = "x-v2")]
+use crate::oauth2;
+
+/// an archive on disk, read like the command line reads it: tweets.js or tweets.json, an archive
+/// directory, a glob over tweets-partN.js or the downloaded zip.
+pub struct ArchiveLoader {
+    path: PathBuf,
+}
+
+impl ArchiveLoader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// every entry of the archive, each with the post under `tweet`.
+    pub fn load(&self) -> Result<Vec<Value>> {
+        get_entries(&self.path)
+    }
+
+    /// where a run over this archive keeps the posts it hasn't processed yet.
+    pub fn state_path(&self) -> PathBuf {
+        state_path_for(&self.path)
+    }
+}
+
+/// the deletions of the default run, for bots, GUIs and schedulers to embed. pacing, retries,
+/// backups and the ledger all work as they do on the command line, which runs on this too.
+pub struct DeletionEngine {
+    options: RunOptions,
+    state_path: PathBuf,
+}
+
+/// how a `DeletionEngine` runs. everything not set is what the command line defaults to.
+/// credentials come from the environment, as they do there.
+pub struct EngineBuilder {
+    options: RunOptions,
+    state_path: PathBuf,
+}
+
+impl EngineBuilder {
+    /// accept every confirmation prompt, as --yes.
+    pub fn yes(mut self, yes: bool) -> Self {
+        self.options.yes = yes;
+        self
+    }
+
+    /// list the posts instead of deleting them, as --dry-run.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// run against a simulated API and clock, as --simulate.
+    pub fn simulate(mut self, simulate: bool) -> Self {
+        self.options.simulate = simulate;
+        self
+    }
+
+    /// how many posts are deleted at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.options.concurrency = concurrency;
+        self
+    }
+
+    /// maximum requests per second.
+    pub fn max_qps(mut self, max_qps: f64) -> Self {
+        self.options.max_qps = Some(max_qps);
+        self
+    }
+
+    /// share of the last 20 requests that may fail before the concurrency is halved, or at 1, the
+    /// pause between posts doubled.
+    pub fn slowdown_error_rate(mut self, rate: f64) -> Self {
+        self.options.slowdown_error_rate = rate;
+        self
+    }
+
+    /// how long to wait before trying a post again that failed transiently, and how often.
+    pub fn retry(mut self, after: chrono::Duration, attempts: u32) -> Self {
+        self.options.retry_after = after;
+        self.options.retry_attempts = attempts;
+        self
+    }
+
+    /// longest single rate-limit wait.
+    pub fn max_wait(mut self, max_wait: std::time::Duration) -> Self {
+        self.options.max_wait = max_wait;
+        self
+    }
+
+    /// keep roughly this many bytes of the posts in memory and spill the rest, as --max-memory.
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.options.max_memory = Some(bytes);
+        self
+    }
+
+    /// also rewrite the state file every `every` processed posts.
+    pub fn checkpoint_every(mut self, every: usize) -> Self {
+        self.options.checkpoint_every = Some(every);
+        self
+    }
+
+    /// append one JSON line per processed post.
+    pub fn ledger(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.ledger = Some(path.into());
+        self
+    }
+
+    /// append one JSON line per attempted deletion.
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.audit_log = Some(path.into());
+        self
+    }
+
+    /// hash the text in the ledger and the audit log instead of writing it.
+    pub fn redact_text(mut self, redact_text: bool) -> Self {
+        self.options.redact_text = redact_text;
+        self
+    }
+
+    /// keep a JSON file with the counts and an ETA up to date.
+    pub fn progress_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.progress_file = Some(path.into());
+        self
+    }
+
+    /// write the summary of the run as JSON.
+    pub fn report(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.report = Some(path.into());
+        self
+    }
+
+    /// list what has to be done by hand when the run ends.
+    pub fn manual_actions(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.manual_actions = Some(path.into());
+        self
+    }
+
+    /// back up every post and its media here before deleting it, `concurrency` at a time.
+    pub fn backup(mut self, dir: impl Into<PathBuf>, concurrency: usize) -> Self {
+        self.options.backup_dir = Some(dir.into());
+        self.options.backup_concurrency = concurrency;
+        self
+    }
+
+    /// write the requests and their responses as HAR.
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.record = Some(path.into());
+        self
+    }
+
+    /// authorize with the token file `auth --oauth2` wrote instead of the OAuth 1.0a keys.
+    #[cfg(feature = "x-v2")]
+    pub fn oauth2(mut self, token_file: impl Into<PathBuf>) -> Self {
+        self.options.auth = AuthMethod::Oauth2;
+        self.options.oauth2_token_file = token_file.into();
+        self
+    }
+
+    /// checks what the command line checks while parsing.
+    pub fn build(self) -> Result<DeletionEngine> {
+        let options = &self.options;
+        if let Some(qps) = options.max_qps {
+            anyhow::ensure!(qps > 0.0 && qps.is_finite(), "expect a positive max_qps. value={}", qps);
+        }
+        anyhow::ensure!((0.0..=1.0).contains(&options.slowdown_error_rate), "expect a slowdown_error_rate between 0 and 1. value={}", options.slowdown_error_rate);
+        anyhow::ensure!(options.checkpoint_every != Some(0), "expect checkpoint_every to be at least 1.");
+        Ok(DeletionEngine::from_options(self.options, self.state_path))
+    }
+}
+
+impl DeletionEngine {
+    /// what is left when a run stops goes to `state_path`, e.g. `ArchiveLoader::state_path`.
+    pub fn builder(state_path: impl Into<PathBuf>) -> EngineBuilder {
+        EngineBuilder { options: RunOptions::default(), state_path: state_path.into() }
+    }
+
+    /// deletes `posts`, entries of an archive, in order. every event of the run goes to `events`
+    /// as the JSON object --sink would get. what's left when the run stops is in the state path.
+    pub async fn run(&self, posts: Vec<Value>, events: mpsc::UnboundedSender<Value>) -> Result<()> {
+        let metadata = new_metadata(None, Vec::new(), posts.len(), posts.len());
+        let sinks: Vec<Box<dyn events::Sink>> = vec![Box::new(events::Channel(events))];
+        self.execute(posts, metadata, new_clock(&self.options), sinks).await
+    }
+}
+
+/// a run of the command line with its `options`. see `DeletionEngine::execute`.
+pub(crate) async fn run(posts: Vec<Value>, state_path: PathBuf, metadata: RunMetadata, options: &RunOptions, clock: SharedClock) -> Result<()> {
+    DeletionEngine::from_options(options.clone(), state_path).execute(posts, metadata, clock, Vec::new()).await
+}
+
+struct ProcessedValue {
+    data: Vec<Value>,
+    // kept in the remaining file so a later run retries them.
+    failed: Vec<Value>,
+    // None when nothing may be written, as with --simulate.
+    name: Option<PathBuf>,
+    // what left `data` since the last flush.
+    journal: Option<Journal>,
+    metadata: RunMetadata,
+    // whether `name` as it was before the run has been copied to `.bak`, or didn't exist.
+    original_saved: bool,
+    // --checkpoint-every, and how many posts were processed since the last flush.
+    checkpoint_every: Option<usize>,
+    since_flush: usize,
+}
+
+impl ProcessedValue {
+    fn new(data: Vec<Value>, name: Option<PathBuf>, metadata: RunMetadata) -> Self {
+        Self {
+            data,
+            failed: Vec::new(),
+            journal: name.as_deref().map(Journal::new),
+            name,
+            metadata,
+            original_saved: false,
+            checkpoint_every: None,
+            since_flush: 0,
+        }
+    }
+
+    fn record_outcome(&mut self, tweet: &Value, outcome: &str) {
+        if let Some(id) = thread::id(tweet) {
+            self.metadata.outcomes.insert(id, outcome.to_string());
+        }
+        self.since_flush += 1;
+    }
+
+    /// the journal only knows what left `data`. failures, outcomes and counts reach the file here.
+    fn checkpoint(&mut self) {
+        if self.checkpoint_every.is_some_and(|every| self.since_flush >= every) {
+            self.flush();
+        }
+    }
+
+    fn record_removal(&mut self, tweet: &Value) {
+        let (Some(journal), Some(id)) = (&mut self.journal, thread::id(tweet)) else {
+            return;
+        };
+        journal.record(id);
+        if journal.len() >= journal::COMPACT_EVERY {
+            self.flush();
+        }
+    }
+
+    /// where `id` is in `data`. posts mostly leave in order, so this is usually the front.
+    fn position(&self, id: u64) -> Option<usize> {
+        self.data.iter().position(|tweet| thread::id(tweet) == Some(id))
+    }
+
+    fn process(&mut self, id: u64, outcome: &str) {
+        if let Some(index) = self.position(id) {
+            let tweet = self.data.remove(index);
+            self.metadata.processed += 1;
+            self.record_outcome(&tweet, outcome);
+            self.record_removal(&tweet);
+            self.checkpoint();
+        }
+    }
+
+    fn fail(&mut self, id: u64, outcome: &str) {
+        if let Some(index) = self.position(id) {
+            let tweet = self.data.remove(index);
+            self.record_outcome(&tweet, outcome);
+            self.failed.push(tweet);
+            self.metadata.processed += 1;
+            self.metadata.failed += 1;
+            self.checkpoint();
+        }
+    }
+
+    /// a failed post that went through on a retry.
+    fn recover(&mut self, id: u64, outcome: &str) {
+        if let Some(index) = self.failed.iter().position(|tweet| thread::id(tweet) == Some(id)) {
+            let tweet = self.failed.remove(index);
+            self.metadata.failed -= 1;
+            self.record_outcome(&tweet, outcome);
+            self.record_removal(&tweet);
+            self.checkpoint();
+        }
+    }
+}
+
+impl ProcessedValue {
+    fn flush(&mut self) {
+        self.since_flush = 0;
+        self.metadata.remaining = self.data.len();
+        let mut parts = BTreeMap::new();
+        for part in self.data.iter().chain(&self.failed).filter_map(|tweet| tweet["part"].as_str()) {
+            *parts.entry(part.to_string()).or_insert(0) += 1;
+        }
+        self.metadata.parts = parts;
+        let Some(name) = self.name.clone() else {
+            return;
+        };
+        if !self.original_saved {
+            match save_original(&name) {
+                std::result::Result::Ok(()) => self.original_saved = true,
+                // better to keep going with the journal than to overwrite the only copy.
+                Err(err) => return eprintln!("failed to back up {}. not rewritten. err={:#}", name.display(), err),
+            }
+        }
+        let tweets: Vec<&Value> = self.data.iter().chain(&self.failed).collect();
+        let output = json!({ "metadata": &self.metadata, "tweets": tweets });
+        match write_atomically(&name, &output) {
+            std::result::Result::Ok(()) => {
+                if let Some(journal) = &mut self.journal {
+                    journal.clear();
+                }
+            },
+            Err(err) => eprintln!("failed to write {}. err={:#}", name.display(), err),
+        }
+    }
+}
+
+impl Drop for ProcessedValue {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// absolute ceiling on the request rate, applied on top of the rate-limit headers.
+/// shared by the workers of --concurrency, so a rate limit one of them runs into holds all of them
+/// that go to the same endpoint.
+struct Throttle {
+    interval: Option<tokio::time::Duration>,
+    /// when the last request was let through, or is going to be.
+    last: Mutex<Option<DateTime<Utc>>>,
+    /// the 24-hour budget as the server last reported it.
+    quota: Mutex<DailyQuota>,
+    /// the 15-minute windows by `Action::name`.
+    windows: Mutex<HashMap<&'static str, Window>>,
+    /// the end of the rate-limit wait a worker is in, by `Action::name`.
+    paused_until: Mutex<HashMap<&'static str, DateTime<Utc>>>,
+}
+
+impl Throttle {
+    fn new(max_qps: Option<f64>) -> Self {
+        Self {
+            interval: max_qps.map(|qps| tokio::time::Duration::from_secs_f64(1.0 / qps)),
+            last: Mutex::new(None),
+            quota: Mutex::new(DailyQuota::default()),
+            windows: Mutex::new(HashMap::new()),
+            paused_until: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn pause_until(&self, action: Action, resumes_at: DateTime<Utc>) {
+        let mut paused_until = self.paused_until.lock().expect("throttle lock poisoned.");
+        let paused_until = paused_until.entry(action.name()).or_insert(resumes_at);
+        *paused_until = (*paused_until).max(resumes_at);
+    }
+
+    fn exhausted_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.quota.lock().expect("throttle lock poisoned.").exhausted_until(now)
+    }
+
+    /// what the window of `action` has left. see `Window::left`.
+    fn left(&self, action: Action, now: DateTime<Utc>) -> u64 {
+        self.windows.lock().expect("throttle lock poisoned.").get(action.name()).map_or(u64::MAX, |window| window.left(now))
+    }
+
+    fn record(&self, action: Action, headers: &reqwest::header::HeaderMap) -> Option<u64> {
+        self.windows.lock().expect("throttle lock poisoned.").entry(action.name()).or_default().update(headers);
+        let mut quota = self.quota.lock().expect("throttle lock poisoned.");
+        quota.update(headers);
+        quota.remaining
+    }
+
+    async fn wait(&self, clock: &SharedClock, action: Action) {
+        loop {
+            let paused_until = self.paused_until.lock().expect("throttle lock poisoned.").get(action.name()).copied();
+            match paused_until.filter(|paused_until| *paused_until > clock.now()) {
+                Some(paused_until) => clock.sleep((paused_until - clock.now()).to_std().unwrap_or_default()).await,
+                None => break,
+            }
+        }
+        // the slot is taken before sleeping, so concurrent workers line up behind each other.
+        let slot = {
+            let mut last = self.last.lock().expect("throttle lock poisoned.");
+            let now = clock.now();
+            let slot = match (self.interval, *last) {
+                (Some(interval), Some(last)) => (last + chrono::Duration::from_std(interval).unwrap_or_default()).max(now),
+                _ => now,
+            };
+            *last = Some(slot);
+            slot
+        };
+        clock.sleep((slot - clock.now()).to_std().unwrap_or_default()).await;
+    }
+}
+
+enum Outcome {
+    Deleted,
+    NotFound,
+    Failed,
+    /// failed, but likely to work a little later. see `retry::TRANSIENT`.
+    Retry,
+    /// a 401. ends the run, since every following request would get one too.
+    Unauthorized,
+}
+
+impl Outcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Deleted => "deleted",
+            Outcome::NotFound => "not_found",
+            Outcome::Failed => "failed",
+            Outcome::Retry => "retry_later",
+            Outcome::Unauthorized => "unauthorized",
+        }
+    }
+}
+
+/// Retry-After is either delta-seconds or an HTTP-date. a date in the past means retry now.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<tokio::time::Duration> {
+    if let std::result::Result::Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(tokio::time::Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?.to_utc();
+    Some((date - now).to_std().unwrap_or_default())
+}
+
+/// sleeps for a rate limit, but never longer than `max_wait`. a longer wait almost always comes
+/// from a bogus header, so the request is simply tried again after the cap.
+/// on a terminal the remaining time is counted down in place, so a long wait doesn't look like a hang.
+async fn rate_limit_sleep(duration: tokio::time::Duration, reason: &str, action: Action, pacing: &Pacing<'_>) {
+    let Pacing { clock, max_wait, progress, status, events, throttle, bar, waits, .. } = pacing;
+    if duration > *max_wait {
+        pacing.eprintln(format_args!("{} rate-limit wait capped. requested={}s max_wait={}s", crate::color::warning(), duration.as_secs(), max_wait.as_secs()));
+    }
+    let duration = duration.min(*max_wait);
+    waits.record(duration, reason);
+    let resumes_at = clock.now() + chrono::Duration::from_std(duration).unwrap_or_default();
+    tracing::warn!(action = action.name(), reason, wait_seconds = duration.as_secs(), resumes_at = %resumes_at, "rate limited");
+    throttle.pause_until(action, resumes_at);
+    status.lock().expect("status lock poisoned.").waiting = Some((reason.to_string(), std::time::Instant::now()));
+    events.emit(Event::Waiting { reason, resumes_at });
+    if let Some(progress) = progress {
+        progress.waiting(reason, resumes_at).unwrap_or_else(|err| pacing.eprintln(format_args!("failed to update progress file. err={:#}", err)));
+    }
+    if let Some(bar) = bar {
+        // the bar counts it down instead.
+        bar.waiting(reason, resumes_at, duration);
+        clock.sleep(duration).await;
+        bar.resumed();
+    } else if io::stdout().is_terminal() && !clock.is_simulated() {
+        loop {
+            let left = (resumes_at - clock.now()).to_std().unwrap_or_default();
+            print!("\rwaiting for {}. resumes at {}. {} left ", reason, resumes_at.format("%Y-%m-%d %H:%M:%S UTC"), format_remaining(left));
+            io::stdout().flush().ok();
+            if left.is_zero() {
+                println!();
+                break;
+            }
+            clock.sleep(left.min(tokio::time::Duration::from_secs(1))).await;
+        }
+    } else {
+        clock.sleep(duration).await;
+    }
+    status.lock().expect("status lock poisoned.").waiting = None;
+}
+
+/// what the waits of a run need besides the duration.
+struct Pacing<'a> {
+    clock: &'a SharedClock,
+    max_wait: tokio::time::Duration,
+    progress: Option<&'a ProgressFile>,
+    status: &'a SharedStatus,
+    events: &'a Events,
+    throttle: &'a Throttle,
+    bar: Option<&'a Bar>,
+    recorder: Option<&'a Recorder>,
+    audit: Option<&'a AuditLog>,
+    waits: &'a Waits,
+}
+
+impl Pacing<'_> {
+    /// lines of the run go above the --progress-bar while it's shown, and into --log-file at debug.
+    fn println(&self, line: std::fmt::Arguments) {
+        tracing::debug!("{}", line);
+        match self.bar {
+            Some(bar) => bar.println(line),
+            None => println!("{}", line),
+        }
+    }
+
+    fn eprintln(&self, line: std::fmt::Arguments) {
+        tracing::warn!("{}", line);
+        match self.bar {
+            Some(bar) => bar.eprintln(line),
+            None => eprintln!("{}", line),
+        }
+    }
+}
+
+/// `api.delete`, recorded with --record.
+async fn send(id: u64, action: Action, api: &Api, pacing: &Pacing<'_>) -> Result<Response> {
+    let (started_at, started) = (pacing.clock.now(), std::time::Instant::now());
+    let response = api.delete(id, action).await
+        .inspect_err(|err| tracing::error!(id, action = action.name(), error = %format!("{:#}", err), "request failed"))?;
+    tracing::info!(id, action = action.name(), status = response.status().as_u16(), elapsed_ms = started.elapsed().as_millis() as u64, "response");
+    match pacing.recorder {
+        Some(recorder) => {
+            let (method, url) = api.request(id, action);
+            recorder.record(method, &url, started_at, started.elapsed(), response).await
+        },
+        None => Ok(response),
+    }
+}
+
+/// the outcome, and the status of the response that decided it.
+/// waits as long as the headers of a 429 say: Retry-After, else x-rate-limit-reset, else a full window.
+async fn wait_out_429(headers: &HeaderMap, action: Action, pacing: &Pacing<'_>) {
+    let retry_after = headers.get("Retry-After").and_then(|value| value.to_str().ok());
+    let reset = headers.get("x-rate-limit-reset").and_then(|value| value.to_str().ok());
+    if let Some((retry_time_str, sleep_duration)) = retry_after.and_then(|value| Some((value, parse_retry_after(value, pacing.clock.now())?))) {
+        pacing.println(format_args!("wait for rate limit. Retry-After={}", retry_time_str));
+        rate_limit_sleep(sleep_duration, "Retry-After", action, pacing).await;
+    } else if let Some((timestamp_str, naive)) = reset.and_then(|value| Some((value, DateTime::from_timestamp(value.parse::<i64>().ok()?, 0)?))) {
+        // a reset already in the past is a retry right away.
+        let sleep_duration = (naive - pacing.clock.now()).to_std().unwrap_or_default();
+        pacing.println(format_args!("wait till {}. x-rate-limit-reset={}", naive, timestamp_str));
+        rate_limit_sleep(sleep_duration, "x-rate-limit-reset", action, pacing).await;
+    } else {
+        // nothing says how long. a window of v1.1 is the longest it could be.
+        pacing.eprintln(format_args!("{} 429 without a usable Retry-After or x-rate-limit-reset. waiting a full window. Retry-After={:?} x-rate-limit-reset={:?}", crate::color::warning(), retry_after, reset));
+        rate_limit_sleep(tokio::time::Duration::from_secs(15 * 60), "429", action, pacing).await;
+    }
+}
+
+#[cfg(feature = "x-v2")]
+/// what a listing made before a run, such as that of `bookmarks`, waits out its 429s with: the
+/// run's clock and --max-wait, the waiting state of --progress-file and the status dump.
+pub(crate) struct Listing {
+    clock: SharedClock,
+    max_wait: tokio::time::Duration,
+    progress: Option<ProgressFile>,
+    status: SharedStatus,
+    events: Events,
+    throttle: Throttle,
+    waits: Waits,
+}
+
+#[cfg(feature = "x-v2")]
+impl Listing {
+    pub(crate) fn new(options: &RunOptions, account_id: &str, run_id: &str, clock: SharedClock) -> Result<Self> {
+        let progress = options.progress_file.clone()
+            .filter(|_| !options.simulate)
+            .map(|path| ProgressFile::new(path, account_id.to_string(), run_id.to_string(), clock.clone()))
+            .transpose()?;
+        let status: SharedStatus = Arc::new(Mutex::new(Status { run_id: run_id.to_string(), ..Status::default() }));
+        status::listen(status.clone());
+        let events = Events::new(&[], run_id, clock.clone())?;
+        Ok(Self { clock, max_wait: options.max_wait, progress, status, events, throttle: Throttle::new(None), waits: Waits::default() })
+    }
+
+    /// waits out a 429 of a request for `action`, or what the listing is for.
+    pub(crate) async fn rate_limited(&self, headers: &HeaderMap, action: Action) {
+        let pacing = Pacing {
+            clock: &self.clock, max_wait: self.max_wait, progress: self.progress.as_ref(), status: &self.status, events: &self.events,
+            throttle: &self.throttle, bar: None, recorder: None, audit: None, waits: &self.waits,
+        };
+        wait_out_429(headers, action, &pacing).await;
+    }
+}
+
+/// `tweet` is the `tweet` object of the entry. fails only when --audit-log can't be written.
+async fn delete_task(id: u64, action: Action, tweet: &Value, api: &Api, pacing: &Pacing<'_>) -> Result<(Outcome, Option<u16>)> {
+    let Pacing { status, throttle, audit, .. } = pacing;
+    loop {
+        throttle.wait(pacing.clock, action).await;
+        if let Some(reset) = throttle.exhausted_until(pacing.clock.now()) {
+            let sleep_duration = (reset - pacing.clock.now()).to_std().unwrap_or_default();
+            pacing.println(format_args!("wait till {}. daily limit used up. x-user-limit-24hour-reset={}", reset, reset.timestamp()));
+            rate_limit_sleep(sleep_duration, "x-user-limit-24hour-reset", action, pacing).await;
+            continue;
+        }
+        let response = match send(id, action, api, pacing).await {
+            std::result::Result::Ok(response) => response,
+            Err(err) => {
+                pacing.eprintln(format_args!("failed to delete post. id={} err={:#}", id, err));
+                status.lock().expect("status lock poisoned.").last_error = Some(format!("id={} err={:#}", id, err));
+                return Ok((Outcome::Failed, None));
+            },
+        };
+        let daily_remaining = throttle.record(action, response.headers());
+        status.lock().expect("status lock poisoned.").daily_remaining = daily_remaining;
+        if response.status().is_success() {
+            pacing.println(format_args!("{}. id={}", action.done(), id));
+            return Ok((Outcome::Deleted, Some(response.status().as_u16())));
+        } else if response.status().as_u16() == 429 {
+            // tried again below, but it was an attempt all the same.
+            if let Some(audit) = audit {
+                audit.append(id, tweet, action.name(), Some(429), "rate-limited")?;
+            }
+            if throttle.exhausted_until(pacing.clock.now()).is_some() {
+                // the 15-minute reset would only come back to another 429. waited for at the top.
+                continue;
+            }
+            wait_out_429(response.headers(), action, pacing).await;
+            continue;
+        } else if response.status().as_u16() == 404 {
+            // processed_dataから消す為に戻す
+            pacing.println(format_args!("not found. id={}", id));
+            return Ok((Outcome::NotFound, Some(404)));
+        } else if response.status().as_u16() == 401 {
+            pacing.eprintln(format_args!("unauthorized. check the credentials. stopping. id={} status={}", id, response.status()));
+            status.lock().expect("status lock poisoned.").last_error = Some(format!("id={} status={}", id, response.status()));
+            return Ok((Outcome::Unauthorized, Some(401)));
+        } else if retry::TRANSIENT.contains(&response.status().as_u16()) {
+            pacing.eprintln(format_args!("failed to delete post. transient. id={} status={}", id, response.status()));
+            status.lock().expect("status lock poisoned.").last_error = Some(format!("id={} status={}", id, response.status()));
+            return Ok((Outcome::Retry, Some(response.status().as_u16())));
+        } else {
+            pacing.eprintln(format_args!("failed to delete post. id={} status={}", id, response.status()));
+            status.lock().expect("status lock poisoned.").last_error = Some(format!("id={} status={}", id, response.status()));
+            return Ok((Outcome::Failed, Some(response.status().as_u16())));
+        }
+    }
+}
+
+impl DeletionEngine {
+    /// the engine the command line runs, with its flags as they were parsed.
+    pub(crate) fn from_options(options: RunOptions, state_path: PathBuf) -> Self {
+        Self { options, state_path }
+    }
+
+    /// deletes `posts` in order. whatever is left is written to the state path on exit.
+    /// with --workdir the remaining file is written there instead. with --simulate nothing is written
+    /// and `clock` is expected to be a simulated one. events also go to `sinks` besides those of --sink.
+    pub(crate) async fn execute(&self, mut posts: Vec<Value>, mut metadata: RunMetadata, clock: SharedClock, sinks: Vec<Box<dyn events::Sink>>) -> Result<()> {
+        let (options, state_path) = (&self.options, self.state_path.clone());
+        // the input is read and selected from, which is all starting up takes. whatever comes next,
+        // a dry run or an empty selection included, the service manager shouldn't wait any longer.
+        systemd::notify("READY=1");
+        if options.dry_run {
+            for tweet in &posts {
+                let data = &tweet["tweet"];
+                let text = if options.redact_text { text::redact(text::of(data)) } else { report::preview(&text::display(data)) };
+                println!("would delete. id={} {} text={}", data["id"].as_str().unwrap_or_default(), options.timezone.fields(data["created_at"].as_str().unwrap_or_default()), text);
+            }
+            println!("dry run. candidates={} total={}", posts.len(), metadata.total);
+            return Ok(());
+        }
+        let archive_media = state_path.parent().map(|dir| dir.join("tweets_media")).filter(|dir| dir.is_dir());
+        let mut options = options.clone();
+        if options.simulate {
+            options.workdir = None;
+            options.ledger = None;
+            options.audit_log = None;
+            options.progress_file = None;
+            options.record = None;
+            options.report = None;
+            options.manual_actions = None;
+            options.backup_dir = None;
+            options.sinks.retain(|sink| !sink.is_external());
+        }
+        let state_path = match options.apply_workdir()? {
+            Some(workdir) => workdir.join("remaining.json"),
+            None => state_path,
+        };
+        let options = &options;
+
+        let (api, account_id) = if options.simulate {
+            println!("simulate. requests go to a simulated API and nothing is written.");
+            if options.concurrency > 1 {
+                eprintln!("{} the simulated clock adds up the waits of all workers, so took= doesn't show the speedup of --concurrency.", crate::color::warning());
+            }
+            let api = match &options.replay {
+                Some(path) => Api::Replayed { replay: replay::Replay::load(path)?, version: options.api_version },
+                None => Api::simulated(clock.clone()),
+            };
+            (api, "simulated".to_string())
+        } else {
+            let (api, Account { id: account_id, screen_name, .. }) = match options.auth {
+                AuthMethod::Oauth1 => {
+                    let mut credentials = Credentials::from_env()?;
+                    credentials.debug_auth = options.debug_auth.clone().map(signing::DebugAuth);
+                    let account = verify_credentials(&credentials).await?;
+                    (Api::X { credentials, version: options.api_version, user_id: account.id.clone() }, account)
+                },
+                #[cfg(feature = "x-v2")]
+                AuthMethod::Oauth2 => {
+                    let session = oauth2::Session::load(&options.oauth2_token_file)?;
+                    let account = session.account().await?;
+                    (Api::OAuth2 { session, user_id: account.id.clone() }, account)
+                },
+            };
+            if let Some(bound) = metadata.account_id.as_ref().filter(|bound| **bound != account_id) {
+                anyhow::bail!("state file belongs to another account. path={} expected={} actual={}", state_path.display(), bound, account_id);
+            }
+            println!("authenticated. id={} screen_name={}", account_id, screen_name);
+            metadata.account_id = Some(account_id.clone());
+
+            let prompt = match &metadata.cutoff {
+                Some(cutoff) => format!("delete {} posts created before {}?", posts.len(), cutoff),
+                None => format!("delete {} posts?", posts.len()),
+            };
+            if options.web_ui.is_none() && !confirm(options, &prompt)? {
+                return Err(Error::Cancelled.into());
+            }
+            (api, account_id)
+        };
+        let started = clock.now();
+        let run_id = metadata.run_id.clone();
+        println!("run started. run_id={} candidates={}", run_id, posts.len());
+        tracing::info!(run_id, candidates = posts.len(), account_id, "run started");
+        let mut events = Events::new(&options.sinks, &run_id, clock.clone())?;
+        sinks.into_iter().for_each(|sink| events.add(sink));
+        events.emit(Event::RunStarted { candidates: posts.len(), account_id: &account_id });
+
+        let running = running_flag();
+
+        if options.daemon {
+            // stable, so replies still go before their parents when both expire at the same time.
+            posts.sort_by_key(expiry::expires_at);
+        }
+        // the remaining file needs every candidate in memory. with --max-memory the copies the loop and
+        // the backups go through take a quarter of it each and spill to temporary files beyond that.
+        let budget = options.max_memory.map(|max_memory| max_memory / 4);
+        let mut ordered = Vec::new();
+        let mut batches = Vec::new();
+        for (name, batch) in make_batches(posts, options.batch_by) {
+            let mut spool = Spool::new(budget);
+            for tweet in batch {
+                // in batch order, so the remaining file has what comes next first.
+                ordered.push(tweet.clone());
+                spool.push(tweet)?;
+            }
+            if spool.is_spilled() {
+                println!("spilled to disk. batch={} posts={} max_memory={}", name, spool.len(), options.max_memory.unwrap_or_default());
+            }
+            batches.push((name, spool));
+        }
+        let rehost = options.rehost.as_deref().map(|target| Rehost::parse(target, options.rehost_base_url.clone())).transpose()?;
+        let mut backups = match options.backup_dir.clone() {
+            Some(dir) => {
+                // the same posts the loop below gets to, in the same order. likes aren't ours to back up.
+                let mut posts = Spool::new(budget);
+                for tweet in ordered.iter().filter(|tweet| !matches!(Action::of(tweet), Action::Unlike)) {
+                    posts.push(tweet["tweet"].clone())?;
+                }
+                let posts = posts.into_entries()?.filter_map(|tweet| Some((tweet["id"].as_str()?.parse::<u64>().ok()?, tweet)));
+                Some(Pipeline::start(Backup::new(dir, archive_media, rehost), posts, options.backup_concurrency, options.backup_queue))
+            },
+            None => None,
+        };
+        let mut processed_data = ProcessedValue::new(ordered, (!options.simulate).then_some(state_path), metadata);
+        processed_data.checkpoint_every = options.checkpoint_every;
+
+        let throttle = Throttle::new(options.max_qps);
+        let mut pace = pace::Pace::new(tokio::time::Duration::from_secs(3), options.concurrency, options.slowdown_error_rate);
+        let rotation = Rotation { max_size: options.rotate_size, keep: options.rotate_keep };
+        let ledger = options.ledger.clone().map(|path| Ledger::new(path, rotation, options.redact_text));
+        let audit = options.audit_log.clone().map(|path| AuditLog::new(path, rotation, options.redact_text, &run_id, clock.clone()));
+        let mut manual = options.manual_actions.clone().map(|path| ManualActions::new(path, &run_id, options.redact_text));
+        let progress = options.progress_file.clone().map(|path| ProgressFile::new(path, account_id.clone(), run_id.clone(), clock.clone())).transpose()?;
+        if let Some(progress) = &progress {
+            progress.update(0, processed_data.data.len(), 0)?;
+        }
+        let status: SharedStatus = Arc::new(Mutex::new(Status { run_id: run_id.clone(), remaining: processed_data.data.len(), ..Status::default() }));
+        status::listen(status.clone());
+        let recorder = options.record.clone().map(|path| Recorder::new(path, &account_id));
+        let waits = Waits::default();
+        let mut summary = Summary::new(&run_id, started);
+        summary.skipped = processed_data.metadata.total.saturating_sub(processed_data.metadata.candidates);
+        let bar = options.progress_bar.then(|| {
+            let metadata = &processed_data.metadata;
+            Bar::new(metadata.processed, metadata.processed + processed_data.data.len(), clock.clone(), options.timezone)
+                .or_else(|| { eprintln!("{} --progress-bar needs stderr to be a terminal. not shown.", crate::color::warning()); None })
+        }).flatten();
+        let pacing = Pacing { clock: &clock, max_wait: options.max_wait, progress: progress.as_ref(), status: &status, events: &events, throttle: &throttle, bar: bar.as_ref(), recorder: recorder.as_ref(), audit: audit.as_ref(), waits: &waits };
+        let mut retries = retry::RetryQueue::new(options.retry_after, options.retry_attempts);
+        let controls = match options.web_ui {
+            Some(addr) => Some(web::serve(addr, &processed_data.data, status.clone(), options.timezone).await?),
+            None => None,
+        };
+        // at most pace.concurrency() posts are in flight. they're settled in order, which processed_data
+        // and the ledger go by.
+        let mut unauthorized = false;
+        'batches: for (name, spool) in batches {
+            let (mut deleted, mut not_found, mut failed) = (0, 0, 0);
+            let batch_len = spool.len();
+            for batch in spool.into_chunks(budget.unwrap_or(u64::MAX))? {
+                let batch = batch?;
+                let mut posts = Schedule::new(&batch);
+                if posts.is_mixed() {
+                    pacing.println(format_args!("interleaving likes with posts by what their rate limits have left. batch={}", name));
+                }
+                let mut in_flight = FuturesOrdered::new();
+                let mut stopped = false;
+                loop {
+                    'fill: while !stopped && in_flight.len() < pace.concurrency() {
+                        // under --daemon a post that hasn't expired yet lets likes go first.
+                        let ready = |post: &Value| !options.daemon || expiry::expires_at(post).is_none_or(|expires_at| expires_at <= clock.now());
+                        let Some(tweet) = posts.next(clock.now(), |action, now| throttle.left(action, now), ready) else { break };
+                        if !wait_while_paused(controls.as_deref(), &running).await {
+                            stopped = true;
+                            break;
+                        }
+                        let data = &tweet["tweet"];
+                        if *data == serde_json::Value::Null {
+                            continue;
+                        }
+                        let Some(id) = data["id"].as_str().and_then(|id| id.parse::<u64>().ok()) else {
+                            // stays in the remaining file, where it can be fixed by hand.
+                            pacing.eprintln(format_args!("{} entry without a readable id. skipped. id={}", crate::color::warning(), data["id"]));
+                            continue;
+                        };
+
+                        if let Some(expires_at) = expiry::expires_at(tweet).filter(|_| options.daemon) {
+                            if expires_at > clock.now() {
+                                pacing.println(format_args!("wait for expiry. id={} expires_at={}", id, expires_at));
+                                systemd::notify(&format!("STATUS=waiting for {} to expire at {}", id, expires_at));
+                            }
+                            // short steps so Ctrl+C still stops the run. a simulated clock jumps right there.
+                            while expires_at > clock.now() {
+                                if !running.load(Ordering::SeqCst) {
+                                    stopped = true;
+                                    break 'fill;
+                                }
+                                let left = (expires_at - clock.now()).to_std().unwrap_or_default();
+                                let step = if clock.is_simulated() { left } else { left.min(tokio::time::Duration::from_secs(1)) };
+                                clock.sleep(step).await;
+                            }
+                        }
+
+                        let action = Action::of(tweet);
+                        // likes aren't in the backup pipeline.
+                        let mut backups = backups.as_mut().filter(|_| !matches!(action, Action::Unlike));
+                        if controls.as_ref().is_some_and(|controls| controls.is_kept(id)) {
+                            if let Some(backups) = &mut backups {
+                                // its backup was started anyway, and the pipeline hands results out in order.
+                                let _ = backups.wait(id).await;
+                            }
+                            in_flight.push_back(async move { anyhow::Ok((tweet, id, action, None, None)) }.boxed());
+                            continue;
+                        }
+                        status.lock().expect("status lock poisoned.").current = Some(id);
+                        let backed_up = match &mut backups {
+                            Some(backups) => backups.wait(id).await
+                                .map_err(|err| {
+                                    pacing.eprintln(format_args!("failed to back up post. kept. id={} err={:#}", id, err));
+                                    status.lock().expect("status lock poisoned.").last_error = Some(format!("backup id={} err={:#}", id, err));
+                                    if let Some(manual) = &mut manual {
+                                        manual.backup_failed(id, data, &err);
+                                    }
+                                })
+                                .is_ok(),
+                            None => true,
+                        };
+                        if !backed_up {
+                            in_flight.push_back(async move { anyhow::Ok((tweet, id, action, Some(Outcome::Failed), None)) }.boxed());
+                            continue;
+                        }
+                        let (api, pacing, clock, delay) = (&api, &pacing, &clock, pace.delay());
+                        in_flight.push_back(async move {
+                            let (outcome, response_status) = delete_task(id, action, &tweet["tweet"], api, pacing).await?;
+                            // each worker keeps the pause between its own posts.
+                            clock.sleep(delay).await;
+                            anyhow::Ok((tweet, id, action, Some(outcome), Some(response_status)))
+                        }.boxed());
+                    }
+                    // `attempt` is the response status of a post a request was sent for.
+                    let Some(settled) = in_flight.next().await else { break };
+                    let (tweet, id, action, outcome, attempt) = settled?;
+                    let Some(outcome) = outcome else {
+                        summary.kept += 1;
+                        pacing.println(format_args!("kept. id={}", id));
+                        events.emit(Event::Processed { id, action: action.name(), outcome: "kept" });
+                        processed_data.process(id, "kept");
+                        publish(&processed_data, &status, progress.as_ref(), bar.as_ref())?;
+                        continue;
+                    };
+                    events.emit(Event::Processed { id, action: action.name(), outcome: outcome.as_str() });
+                    if let Some(ledger) = &ledger {
+                        ledger.append(LedgerRecord::new(id, &tweet["tweet"], outcome.as_str(), &run_id, clock.now()))?;
+                    }
+                    if let (Some(audit), Some(response_status)) = (&audit, attempt) {
+                        audit.append(id, &tweet["tweet"], action.name(), response_status, outcome.as_str())?;
+                    }
+                    pace.record(matches!(outcome, Outcome::Failed | Outcome::Retry));
+                    match outcome {
+                        Outcome::Deleted => {
+                            deleted += 1;
+                            summary.deleted += 1;
+                            processed_data.process(id, outcome.as_str());
+                        },
+                        Outcome::NotFound => {
+                            not_found += 1;
+                            summary.not_found += 1;
+                            processed_data.process(id, outcome.as_str());
+                        },
+                        Outcome::Failed => {
+                            failed += 1;
+                            processed_data.fail(id, outcome.as_str());
+                            if let (Some(manual), Some(response_status)) = (&mut manual, attempt) {
+                                manual.failed(id, &tweet["tweet"], response_status);
+                            }
+                        },
+                        Outcome::Unauthorized => {
+                            // stays in the remaining file for a run with working credentials.
+                            unauthorized = true;
+                            stopped = true;
+                        },
+                        Outcome::Retry => {
+                            // counted as failed, and kept in the remaining file, until a retry goes through.
+                            processed_data.fail(id, outcome.as_str());
+                            match retries.push(id, tweet, 0, clock.now()) {
+                                Some(due) => {
+                                    tracing::info!(id, attempt = 1, reason = "transient", due = %due, "retry scheduled");
+                                    pacing.println(format_args!("retry later. id={} at={}", id, due));
+                                },
+                                None => {
+                                    failed += 1;
+                                    if let (Some(manual), Some(response_status)) = (&mut manual, attempt) {
+                                        manual.failed(id, &tweet["tweet"], response_status);
+                                    }
+                                },
+                            }
+                        },
+                    }
+                    publish(&processed_data, &status, progress.as_ref(), bar.as_ref())?;
+                    systemd::notify(&format!("STATUS=processed {} of {}", processed_data.metadata.processed, processed_data.metadata.candidates));
+                }
+                if stopped {
+                    // what was in flight is settled above, so the remaining file has it right.
+                    pacing.println(format_args!("stop."));
+                    summary.stopped = true;
+                    break 'batches;
+                }
+            }
+            if options.batch_by.is_some() {
+                pacing.println(format_args!("batch {} done. posts={} deleted={} not_found={} failed={}", name, batch_len, deleted, not_found, failed));
+                events.emit(Event::BatchDone { batch: &name, posts: batch_len, deleted, not_found, failed });
+                processed_data.flush();
+            }
+        }
+        while let Some(entry) = retries.pop().filter(|_| !unauthorized) {
+            // short steps so Ctrl+C still stops the run. a simulated clock jumps right there.
+            while entry.due > clock.now() && running.load(Ordering::SeqCst) {
+                let left = (entry.due - clock.now()).to_std().unwrap_or_default();
+                clock.sleep(if clock.is_simulated() { left } else { left.min(tokio::time::Duration::from_secs(1)) }).await;
+            }
+            if !wait_while_paused(controls.as_deref(), &running).await {
+                pacing.println(format_args!("stop."));
+                summary.stopped = true;
+                break;
+            }
+            pacing.println(format_args!("retry. id={} attempt={}", entry.id, entry.attempt));
+            status.lock().expect("status lock poisoned.").current = Some(entry.id);
+            let action = Action::of(&entry.tweet);
+            let (outcome, response_status) = delete_task(entry.id, action, &entry.tweet["tweet"], &api, &pacing).await?;
+            events.emit(Event::Processed { id: entry.id, action: action.name(), outcome: outcome.as_str() });
+            if let Some(ledger) = &ledger {
+                ledger.append(LedgerRecord::new(entry.id, &entry.tweet["tweet"], outcome.as_str(), &run_id, clock.now()))?;
+            }
+            if let Some(audit) = &audit {
+                audit.append(entry.id, &entry.tweet["tweet"], action.name(), response_status, outcome.as_str())?;
+            }
+            pace.record(matches!(outcome, Outcome::Failed | Outcome::Retry));
+            match outcome {
+                Outcome::Deleted => {
+                    summary.deleted += 1;
+                    processed_data.recover(entry.id, outcome.as_str());
+                },
+                Outcome::NotFound => {
+                    summary.not_found += 1;
+                    processed_data.recover(entry.id, outcome.as_str());
+                },
+                Outcome::Retry => match retries.push(entry.id, &entry.tweet, entry.attempt, clock.now()) {
+                    Some(due) => {
+                        tracing::info!(id = entry.id, attempt = entry.attempt + 1, reason = "transient", due = %due, "retry scheduled");
+                        pacing.println(format_args!("retry later. id={} at={}", entry.id, due));
+                    },
+                    None => {
+                        pacing.eprintln(format_args!("failed to delete post. giving up. id={} attempts={}", entry.id, entry.attempt));
+                        if let Some(manual) = &mut manual {
+                            manual.failed(entry.id, &entry.tweet["tweet"], response_status);
+                        }
+                    },
+                },
+                Outcome::Unauthorized => {
+                    unauthorized = true;
+                    summary.stopped = true;
+                    break;
+                },
+                Outcome::Failed => {
+                    if let Some(manual) = &mut manual {
+                        manual.failed(entry.id, &entry.tweet["tweet"], response_status);
+                    }
+                },
+            }
+            publish(&processed_data, &status, progress.as_ref(), bar.as_ref())?;
+            clock.sleep(pace.delay()).await;
+        }
+        if let Some(bar) = &bar {
+            bar.finish();
+        }
+        let metadata = &processed_data.metadata;
+        summary.failed = metadata.failed;
+        summary.remaining = processed_data.data.len();
+        summary.finish(clock.now(), &waits);
+        summary.print(options.simulate);
+        if let Some(path) = &options.report {
+            summary.write(path)?;
+        }
+        if let Some(manual) = &manual {
+            manual.write()?;
+        }
+        tracing::info!(run_id, processed = metadata.processed, failed = metadata.failed, remaining = processed_data.data.len(), "run finished");
+        events.emit(Event::RunFinished { processed: metadata.processed, failed: metadata.failed, remaining: processed_data.data.len() });
+        events.finish().await;
+
+        if unauthorized {
+            return Err(Error::Unauthorized { status: 401 }.into());
+        }
+        if summary.stopped {
+            return Err(Error::Stopped.into());
+        }
+        Ok(())
+    }
+}
+
+/// cleared by Ctrl+C, or Ctrl+Break in a Windows console. the handler is set once per process,
+/// since an embedding application may start more than one run, and reset for every run.
+fn running_flag() -> Arc<AtomicBool> {
+    static RUNNING: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+    let running = RUNNING.get_or_init(|| {
+        let running = Arc::new(AtomicBool::new(true));
+        let r = running.clone();
+        if let Err(err) = ctrlc::set_handler(move || {
+            println!("Ctrl+C received.");
+            r.store(false, Ordering::SeqCst);
+        }) {
+            eprintln!("{} failed to set Ctrl+C handler. a run can't be stopped cleanly. err={}", crate::color::warning(), err);
+        }
+        running
+    });
+    running.store(true, Ordering::SeqCst);
+    running.clone()
+}
+
+/// holds the run while the web ui has it paused. false once Ctrl+C stopped the run.
+async fn wait_while_paused(controls: Option<&web::Controls>, running: &AtomicBool) -> bool {
+    // in real time even under --simulate, since it's a person who unpauses.
+    while controls.is_some_and(|controls| controls.is_paused()) && running.load(Ordering::SeqCst) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
+    running.load(Ordering::SeqCst)
+}
+
+/// mirrors the counts of `processed_data` into the status, the progress file and the bar.
+fn publish(processed_data: &ProcessedValue, status: &SharedStatus, progress: Option<&ProgressFile>, bar: Option<&Bar>) -> Result<()> {
+    let metadata = &processed_data.metadata;
+    {
+        let mut status = status.lock().expect("status lock poisoned.");
+        status.processed = metadata.processed;
+        status.remaining = processed_data.data.len();
+        status.failed = metadata.failed;
+    }
+    if let Some(bar) = bar {
+        bar.update(metadata.processed, metadata.processed + processed_data.data.len());
+    }
+    if let Some(progress) = progress {
+        progress.update(metadata.processed, processed_data.data.len(), metadata.failed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn retry_after_is_seconds_or_a_date() {
+        let now = DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z").unwrap().to_utc();
+        assert_eq!(parse_retry_after("120", now), Some(tokio::time::Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 7 ", now), Some(tokio::time::Duration::from_secs(7)));
+        assert_eq!(parse_retry_after("Tue, 02 Jan 2024 03:05:05 GMT", now), Some(tokio::time::Duration::from_secs(60)));
+        assert_eq!(parse_retry_after(" Tue, 02 Jan 2024 12:05:35 +0900 ", now), Some(tokio::time::Duration::from_secs(90)));
+        // a date that already passed is a retry right away.
+        assert_eq!(parse_retry_after("Tue, 02 Jan 2024 03:00:00 GMT", now), Some(tokio::time::Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("Tue, 02 Jan 2024", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+
+    fn post(id: u64) -> Value {
+        json!({ "tweet": { "id": id.to_string(), "id_str": id.to_string(), "created_at": "Mon Jan 01 10:00:00 +0000 2024", "full_text": "post" } })
+    }
+
+    #[tokio::test]
+    async fn simulated_run_waits_out_the_rate_limit() {
+        // more posts than a simulated window allows, so the run has to wait for the reset.
+        let posts: Vec<Value> = (1..=60).map(post).collect();
+        let engine = DeletionEngine::builder(std::env::temp_dir().join("post_remove-engine-test.state.json"))
+            .simulate(true)
+            .yes(true)
+            .build()
+            .unwrap();
+        let (events, mut received) = mpsc::unbounded_channel();
+        engine.run(posts, events).await.unwrap();
+        let mut records = Vec::new();
+        while let Ok(record) = received.try_recv() {
+            records.push(record);
+        }
+        let deleted = records.iter().filter(|record| record["event"] == "processed" && record["outcome"] == "deleted").count();
+        assert_eq!(deleted, 60);
+        assert!(records.iter().any(|record| record["event"] == "waiting"));
+        let finished = records.last().unwrap();
+        assert_eq!(finished["event"], "run_finished");
+        assert_eq!(finished["remaining"], 0);
+    }
+
+    #[test]
+    fn builder_checks_what_the_flags_check() {
+        let builder = || DeletionEngine::builder("state.json");
+        assert!(builder().max_qps(0.0).build().is_err());
+        assert!(builder().slowdown_error_rate(1.5).build().is_err());
+        assert!(builder().checkpoint_every(0).build().is_err());
+        assert!(builder().max_qps(0.5).concurrency(2).build().is_ok());
+    }
+}
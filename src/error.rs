@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+/// what ends a run, each with its own exit code so a wrapper script can tell them apart. anything
+/// else exits with 1, and a mistake on the command line with clap's 2.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// every following request would fail the same way.
+    #[error("unauthorized. check the credentials. status={status}")]
+    Unauthorized { status: u16 },
+    #[error("failed to parse {}. {reason}", path.display())]
+    Parse { path: PathBuf, reason: String },
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    /// Ctrl+C. what's left is in the state file.
+    #[error("stopped. run again with --resume to continue.")]
+    Stopped,
+    /// no at a confirmation prompt.
+    #[error("cancelled.")]
+    Cancelled,
+}
+
+pub const AUTH: u8 = 3;
+pub const PARSE: u8 = 4;
+pub const NETWORK: u8 = 5;
+/// what shells report for a process ended by SIGINT.
+pub const ABORT: u8 = 130;
+
+impl Error {
+    pub fn parse(path: impl Into<PathBuf>, reason: impl std::fmt::Display) -> Self {
+        Error::Parse { path: path.into(), reason: reason.to_string() }
+    }
+}
+
+/// the exit code for `err`, by the first cause it knows.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<Error>() {
+            return match err {
+                Error::Unauthorized { .. } => AUTH,
+                Error::Parse { .. } => PARSE,
+                Error::Network(_) => NETWORK,
+                Error::Stopped | Error::Cancelled => ABORT,
+            };
+        }
+        if cause.is::<reqwest::Error>() {
+            return NETWORK;
+        }
+        if cause.is::<serde_json::Error>() {
+            return PARSE;
+        }
+    }
+    1
+}
@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs::{File, OpenOptions}, io::Write, path::PathBuf, str::FromStr, sync::Mutex};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::clock::SharedClock;
+
+/// what happened during a run, for operators to route into their own logging. stdout stays as it is.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    RunStarted { candidates: usize, account_id: &'a str },
+    Processed { id: u64, action: &'static str, outcome: &'static str },
+    Waiting { reason: &'a str, resumes_at: DateTime<Utc> },
+    BatchDone { batch: &'a str, posts: usize, deleted: usize, not_found: usize, failed: usize },
+    RunFinished { processed: usize, failed: usize, remaining: usize },
+}
+
+/// somewhere events go. `record` is the event with `at` and `run_id` added, as one JSON object.
+pub trait Sink: Send + Sync {
+    fn emit(&self, record: &Value);
+}
+
+/// one JSON line per event on stderr.
+struct Console;
+
+impl Sink for Console {
+    fn emit(&self, record: &Value) {
+        eprintln!("{}", record);
+    }
+}
+
+/// one JSON line per event appended to a file.
+struct JsonLines {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Sink for JsonLines {
+    fn emit(&self, record: &Value) {
+        let mut file = self.file.lock().expect("event file lock poisoned.");
+        if let Err(err) = writeln!(file, "{}", record) {
+            eprintln!("failed to write event. path={} err={}", self.path.display(), err);
+        }
+    }
+}
+
+/// POSTs every event as JSON, one at a time and in order, without holding up the run.
+struct Webhook {
+    events: mpsc::UnboundedSender<Value>,
+}
+
+impl Webhook {
+    fn start(url: String) -> (Self, JoinHandle<()>) {
+        let (events, mut receiver) = mpsc::unbounded_channel::<Value>();
+        let worker = tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            while let Some(record) = receiver.recv().await {
+                let sent = client.post(&url).json(&record).timeout(std::time::Duration::from_secs(10)).send().await
+                    .and_then(|response| response.error_for_status());
+                if let Err(err) = sent {
+                    eprintln!("failed to send event. url={} err={}", url, err);
+                }
+            }
+        });
+        (Self { events }, worker)
+    }
+}
+
+impl Sink for Webhook {
+    fn emit(&self, record: &Value) {
+        // the worker only goes away with the sink.
+        let _ = self.events.send(record.clone());
+    }
+}
+
+/// hands every event to an embedding application.
+pub struct Channel(pub mpsc::UnboundedSender<Value>);
+
+impl Sink for Channel {
+    fn emit(&self, record: &Value) {
+        // the application stopped listening, which doesn't stop the run.
+        let _ = self.0.send(record.clone());
+    }
+}
+
+/// syslog severities. failures are worth an alert, waits a look.
+fn severity(record: &Value) -> u8 {
+    match (record["event"].as_str(), record["outcome"].as_str()) {
+        (_, Some("failed")) => 4,
+        (Some("waiting"), _) => 5,
+        _ => 6,
+    }
+}
+
+/// the journal's native protocol, so every field of an event can be matched on, e.g.
+/// `journalctl POST_REMOVE_EVENT=processed POST_REMOVE_OUTCOME=failed`.
+#[cfg(target_os = "linux")]
+struct Journald {
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+#[cfg(target_os = "linux")]
+impl Journald {
+    const SOCKET: &'static str = "/run/systemd/journal/socket";
+
+    fn connect() -> anyhow::Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket.connect(Self::SOCKET).map_err(|err| anyhow::anyhow!("failed to connect to journald. path={} err={}", Self::SOCKET, err))?;
+        Ok(Self { socket })
+    }
+
+    fn field(datagram: &mut Vec<u8>, name: &str, value: &str) {
+        datagram.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // values with newlines go length-prefixed.
+            datagram.push(b'\n');
+            datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            datagram.push(b'=');
+        }
+        datagram.extend_from_slice(value.as_bytes());
+        datagram.push(b'\n');
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Sink for Journald {
+    fn emit(&self, record: &Value) {
+        let mut datagram = Vec::new();
+        Self::field(&mut datagram, "MESSAGE", &record.to_string());
+        Self::field(&mut datagram, "PRIORITY", &severity(record).to_string());
+        Self::field(&mut datagram, "SYSLOG_IDENTIFIER", "post_remove");
+        for (key, value) in record.as_object().into_iter().flatten() {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            Self::field(&mut datagram, &format!("POST_REMOVE_{}", key.to_ascii_uppercase()), &value);
+        }
+        if let Err(err) = self.socket.send(&datagram) {
+            eprintln!("failed to send event to journald. err={}", err);
+        }
+    }
+}
+
+/// RFC 3164 lines to the local syslog daemon, facility daemon, with the event as the message.
+#[cfg(unix)]
+struct Syslog {
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+#[cfg(unix)]
+impl Syslog {
+    /// /var/run/syslog is where macOS has it.
+    const SOCKETS: [&'static str; 2] = ["/dev/log", "/var/run/syslog"];
+    const FACILITY: u8 = 3;
+
+    fn connect() -> anyhow::Result<Self> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        Self::SOCKETS.iter().find(|path| socket.connect(path).is_ok())
+            .ok_or_else(|| anyhow::anyhow!("failed to connect to syslog. paths={}", Self::SOCKETS.join(",")))?;
+        Ok(Self { socket })
+    }
+}
+
+#[cfg(unix)]
+impl Sink for Syslog {
+    fn emit(&self, record: &Value) {
+        let line = format!("<{}>{} post_remove[{}]: {}", Self::FACILITY * 8 + severity(record), chrono::Local::now().format("%b %e %H:%M:%S"), std::process::id(), record);
+        if let Err(err) = self.socket.send(line.as_bytes()) {
+            eprintln!("failed to send event to syslog. err={}", err);
+        }
+    }
+}
+
+/// a --sink value: `console`, `file:<path>`, `webhook:<url>`, `journald` or `syslog`.
+#[derive(Clone, Debug)]
+pub enum SinkSpec {
+    Console,
+    File(PathBuf),
+    Webhook(String),
+    Journald,
+    Syslog,
+}
+
+impl SinkSpec {
+    /// whether it writes or sends anything, which --simulate doesn't allow.
+    pub fn is_external(&self) -> bool {
+        !matches!(self, SinkSpec::Console)
+    }
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.split_once(':') {
+            None if value == "console" => Ok(SinkSpec::Console),
+            None if value == "journald" && cfg!(target_os = "linux") => Ok(SinkSpec::Journald),
+            None if value == "syslog" && cfg!(unix) => Ok(SinkSpec::Syslog),
+            None if value == "journald" || value == "syslog" => Err(format!("{} isn't available on this platform.", value)),
+            Some(("file", path)) if !path.is_empty() => Ok(SinkSpec::File(PathBuf::from(path))),
+            Some(("webhook", url)) if url.starts_with("https://") || url.starts_with("http://") => Ok(SinkSpec::Webhook(url.to_string())),
+            _ => Err(format!("expect console, file:<path>, webhook:<url>, journald or syslog. value={}", value)),
+        }
+    }
+}
+
+/// the sinks of a run.
+pub struct Events {
+    run_id: String,
+    /// so `at` lines up with the waits of a simulated run.
+    clock: SharedClock,
+    sinks: Vec<Box<dyn Sink>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Events {
+    pub fn new(specs: &[SinkSpec], run_id: &str, clock: SharedClock) -> anyhow::Result<Self> {
+        let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+        let mut workers = Vec::new();
+        for spec in specs {
+            match spec {
+                SinkSpec::Console => sinks.push(Box::new(Console)),
+                SinkSpec::File(path) => {
+                    let file = OpenOptions::new().create(true).append(true).open(path)
+                        .map_err(|err| anyhow::anyhow!("failed to open event file. path={} err={}", path.display(), err))?;
+                    sinks.push(Box::new(JsonLines { path: path.clone(), file: Mutex::new(file) }));
+                },
+                SinkSpec::Webhook(url) => {
+                    let (webhook, worker) = Webhook::start(url.clone());
+                    sinks.push(Box::new(webhook));
+                    workers.push(worker);
+                },
+                #[cfg(target_os = "linux")]
+                SinkSpec::Journald => sinks.push(Box::new(Journald::connect()?)),
+                #[cfg(unix)]
+                SinkSpec::Syslog => sinks.push(Box::new(Syslog::connect()?)),
+                // refused when parsed.
+                #[allow(unreachable_patterns)]
+                _ => {},
+            }
+        }
+        Ok(Self { run_id: run_id.to_string(), clock, sinks, workers })
+    }
+
+    pub fn add(&mut self, sink: Box<dyn Sink>) {
+        self.sinks.push(sink);
+    }
+
+    pub fn emit(&self, event: Event) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let mut record = serde_json::to_value(&event).expect("events serialize.");
+        record["at"] = Value::String(self.clock.now().to_rfc3339());
+        record["run_id"] = Value::String(self.run_id.clone());
+        for sink in &self.sinks {
+            sink.emit(&record);
+        }
+    }
+
+    /// waits for the webhooks to deliver what was emitted.
+    pub async fn finish(self) {
+        drop(self.sinks);
+        for worker in self.workers {
+            let _ = worker.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sink_specs() {
+        assert!(matches!("console".parse(), Ok(SinkSpec::Console)));
+        assert!(matches!("file:events.jsonl".parse(), Ok(SinkSpec::File(path)) if path.as_os_str() == "events.jsonl"));
+        assert!(matches!("webhook:https://example.com/hook".parse(), Ok(SinkSpec::Webhook(url)) if url == "https://example.com/hook"));
+        #[cfg(target_os = "linux")]
+        assert!(matches!("journald".parse(), Ok(SinkSpec::Journald)));
+    }
+
+    #[test]
+    fn rejects_unknown_sinks() {
+        assert!("file:".parse::<SinkSpec>().is_err());
+        assert!("webhook:ftp://example.com".parse::<SinkSpec>().is_err());
+        assert!("stdout".parse::<SinkSpec>().is_err());
+    }
+}
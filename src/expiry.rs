@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{collections::HashMap, fs::File, path::Path};
+
+use crate::{duration, parse_instant, post_time, thread};
+
+/// `#exp30d` style hashtags. the archive keeps hashtags without the `#`.
+fn hashtag_duration(tweet: &Value) -> Option<chrono::Duration> {
+    tweet["tweet"]["entities"]["hashtags"].as_array()?.iter()
+        .filter_map(|hashtag| hashtag["text"].as_str())
+        .find_map(|text| text.strip_prefix("exp").or_else(|| text.strip_prefix("EXP")).and_then(duration::parse))
+}
+
+/// `{"<id>": "30d" | "2025-06-01" | "<RFC 3339>", ...}`. durations count from the post's creation.
+fn read_sidecar(path: &Path) -> Result<HashMap<u64, String>> {
+    let file = File::open(path).with_context(|| format!("failed to open expiry file. path={}", path.display()))?;
+    let sidecar: HashMap<String, String> = serde_json::from_reader(file)
+        .with_context(|| format!("invalid expiry file. expect {{\"<id>\": \"30d\"}}. path={}", path.display()))?;
+    sidecar.into_iter()
+        .map(|(id, expiry)| Ok((id.parse().with_context(|| format!("invalid id in expiry file. id={}", id))?, expiry)))
+        .collect()
+}
+
+/// keeps the posts that carry an expiry and records it as `expires_at` next to `tweet`, so the
+/// remaining file still knows it on the next run. the sidecar wins over hashtags.
+pub fn annotate(posts: Vec<Value>, sidecar: Option<&Path>) -> Result<Vec<Value>> {
+    let sidecar = sidecar.map(read_sidecar).transpose()?.unwrap_or_default();
+    let mut annotated = Vec::new();
+    for mut tweet in posts {
+        let listed = thread::id(&tweet).and_then(|id| sidecar.get(&id));
+        // a duration needs created_at. a post without a readable one never expires.
+        let expires_at = match listed {
+            Some(expiry) => match duration::parse(expiry) {
+                Some(duration) => post_time(&tweet).map(|created_at| created_at + duration),
+                None => Some(parse_instant(expiry).with_context(|| format!("invalid expiry. expect 30d or a date. value={}", expiry))?),
+            },
+            None => hashtag_duration(&tweet).and_then(|duration| Some(post_time(&tweet)? + duration)),
+        };
+        if let Some(expires_at) = expires_at {
+            tweet["expires_at"] = Value::String(expires_at.to_rfc3339());
+            annotated.push(tweet);
+        }
+    }
+    Ok(annotated)
+}
+
+pub fn expires_at(tweet: &Value) -> Option<DateTime<Utc>> {
+    tweet["expires_at"].as_str().and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok()).map(|time| time.to_utc())
+}
@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs, path::{Path, PathBuf}};
+
+use crate::{backup::BackupRecord, report::preview, text, timezone::{self, Zone}};
+
+fn count(record: &BackupRecord, key: &str) -> u64 {
+    record.tweet[key].as_str().and_then(|count| count.parse().ok()).unwrap_or(0)
+}
+
+/// JSON strings are valid YAML scalars, which saves an escaping routine.
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings always serialize.")
+}
+
+/// front-matter dates and file names are in `zone`, so a post lands on the day it was written there.
+fn markdown_file(record: &BackupRecord, backup_dir: &Path, zone: Zone) -> (String, String) {
+    let created_at = record.tweet["created_at"].as_str().and_then(timezone::parse);
+    let date = created_at.map(|created_at| zone.rfc3339(created_at)).unwrap_or_default();
+    let day = created_at.map(|created_at| zone.format(created_at, "%Y-%m-%d")).unwrap_or_else(|| "unknown".to_string());
+
+    let mut content = String::from("---\n");
+    content += &format!("id: {}\n", yaml_string(&record.id.to_string()));
+    content += &format!("date: {}\n", yaml_string(&date));
+    content += &format!("url: {}\n", yaml_string(&format!("https://x.com/i/status/{}", record.id)));
+    content += &format!("likes: {}\n", count(record, "favorite_count"));
+    content += &format!("retweets: {}\n", count(record, "retweet_count"));
+    content += "---\n\n";
+    content += &text::display(&record.tweet);
+    content += "\n";
+    for media in &record.media {
+        let link = match &media.rehosted_url {
+            Some(url) => url.clone(),
+            None => {
+                let path = backup_dir.join(record.id.to_string()).join(&media.file);
+                fs::canonicalize(&path).unwrap_or(path).display().to_string()
+            },
+        };
+        content += &format!("\n![{}](<{}>)\n", media.file, link);
+    }
+    (format!("{}-{}.md", day, record.id), content)
+}
+
+/// one Markdown file per backed up post, with front-matter, for note systems such as Obsidian.
+pub fn markdown(records: &[BackupRecord], backup_dir: &Path, out: &Path, zone: Zone) -> Result<()> {
+    fs::create_dir_all(out).with_context(|| format!("failed to create {}.", out.display()))?;
+    for record in records {
+        let (name, content) = markdown_file(record, backup_dir, zone);
+        let path = out.join(name);
+        fs::write(&path, content).with_context(|| format!("failed to write {}.", path.display()))?;
+    }
+    println!("exported. format=markdown posts={} out={}", records.len(), out.display());
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+pub enum Blog {
+    Hugo,
+    Jekyll,
+}
+
+pub fn parent(record: &BackupRecord) -> Option<u64> {
+    record.tweet["in_reply_to_status_id_str"].as_str()
+        .or_else(|| record.tweet["in_reply_to_status_id"].as_str())
+        .and_then(|id| id.parse().ok())
+}
+
+/// self-reply chains as one list per thread, oldest post first. a post without a backed up parent starts a thread.
+fn threads(records: &[BackupRecord]) -> Vec<Vec<&BackupRecord>> {
+    let by_id: HashMap<u64, &BackupRecord> = records.iter().map(|record| (record.id, record)).collect();
+    let mut threads: BTreeMap<u64, Vec<&BackupRecord>> = BTreeMap::new();
+    for record in records {
+        let mut root = record;
+        // a self-reply or a reply cycle has no root. the walk stops where it comes round again.
+        let mut seen = HashSet::from([root.id]);
+        while let Some(parent) = parent(root).and_then(|parent| by_id.get(&parent)) {
+            if !seen.insert(parent.id) {
+                break;
+            }
+            root = parent;
+        }
+        threads.entry(root.id).or_default().push(record);
+    }
+    threads.into_values()
+        .map(|mut thread| {
+            thread.sort_by_key(|record| record.id);
+            thread
+        })
+        .collect()
+}
+
+/// copies the media of `record` to `dir` and returns the url to link it under `url_prefix`.
+fn copy_media(record: &BackupRecord, backup_dir: &Path, dir: &Path, url_prefix: &str) -> Result<Vec<(String, String)>> {
+    let mut links = Vec::new();
+    for media in &record.media {
+        if let Some(url) = &media.rehosted_url {
+            links.push((media.file.clone(), url.clone()));
+            continue;
+        }
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}.", dir.display()))?;
+        let source = backup_dir.join(record.id.to_string()).join(&media.file);
+        fs::copy(&source, dir.join(&media.file)).with_context(|| format!("failed to copy {}.", source.display()))?;
+        links.push((media.file.clone(), format!("{}{}", url_prefix, media.file)));
+    }
+    Ok(links)
+}
+
+/// merges every self-reply chain into one blog post. hugo gets a page bundle per thread under
+/// content/posts with the media next to it, jekyll a file under _posts with the media under assets/posts.
+pub fn blog(records: &[BackupRecord], backup_dir: &Path, site: &Path, kind: Blog, zone: Zone) -> Result<()> {
+    let threads = threads(records);
+    for thread in &threads {
+        let root = thread[0];
+        let created_at = root.tweet["created_at"].as_str().and_then(timezone::parse);
+        let date = created_at.map(|created_at| zone.rfc3339(created_at)).unwrap_or_default();
+        let day = created_at.map(|created_at| zone.format(created_at, "%Y-%m-%d")).unwrap_or_else(|| "1970-01-01".to_string());
+        let title = preview(text::display(&root.tweet).lines().next().unwrap_or_default());
+
+        let (path, media_dir, url_prefix): (PathBuf, PathBuf, String) = match kind {
+            Blog::Hugo => {
+                let bundle = site.join("content").join("posts").join(format!("{}-{}", day, root.id));
+                (bundle.join("index.md"), bundle, String::new())
+            },
+            Blog::Jekyll => (
+                site.join("_posts").join(format!("{}-{}.md", day, root.id)),
+                site.join("assets").join("posts").join(root.id.to_string()),
+                format!("/assets/posts/{}/", root.id),
+            ),
+        };
+
+        let mut content = String::from("---\n");
+        if let Blog::Jekyll = kind {
+            content += "layout: post\n";
+        }
+        content += &format!("title: {}\n", yaml_string(&title));
+        content += &format!("date: {}\n", yaml_string(&date));
+        content += &format!("source: {}\n", yaml_string(&format!("https://x.com/i/status/{}", root.id)));
+        content += "---\n";
+        for record in thread {
+            content += "\n";
+            content += &text::display(&record.tweet);
+            content += "\n";
+            for (file, link) in copy_media(record, backup_dir, &media_dir, &url_prefix)? {
+                content += &format!("\n![{}](<{}>)\n", file, link);
+            }
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("failed to create {}.", parent.display()))?;
+        }
+        fs::write(&path, content).with_context(|| format!("failed to write {}.", path.display()))?;
+    }
+    let format = match kind {
+        Blog::Hugo => "hugo",
+        Blog::Jekyll => "jekyll",
+    };
+    println!("exported. format={} posts={} threads={} out={}", format, records.len(), threads.len(), site.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn record(id: u64, reply_to: u64) -> BackupRecord {
+        BackupRecord {
+            schema_version: 0,
+            id,
+            backed_up_at: Utc::now(),
+            tweet: json!({"id_str": id.to_string(), "in_reply_to_status_id_str": reply_to.to_string()}),
+            media: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn threads_end_on_cycles() {
+        let records = vec![record(1, 1), record(2, 3), record(3, 2)];
+        let posts: usize = threads(&records).iter().map(Vec::len).sum();
+        assert_eq!(posts, 3);
+    }
+}
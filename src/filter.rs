@@ -0,0 +1,145 @@
+use chrono::{DateTime, Datelike, Utc};
+use clap::ValueEnum;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{text, thread};
+use std::ops::Not;
+
+/// what a post is, for --only and --keep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PostKind {
+    Retweets,
+    Replies,
+    Originals,
+}
+
+impl PostKind {
+    /// a retweet that is also a reply counts as a retweet, since unretweeting is what removes it.
+    pub fn of(tweet: &Value) -> Self {
+        let post = &tweet["tweet"];
+        let retweet = post["retweeted"].as_bool().unwrap_or(false)
+            || post["retweeted_status"].is_object()
+            || text::of(post).starts_with("RT @");
+        if retweet {
+            PostKind::Retweets
+        } else if thread::parent_id(tweet).is_some() {
+            PostKind::Replies
+        } else {
+            PostKind::Originals
+        }
+    }
+}
+
+/// a condition on an archive entry, built and combined in code instead of parsed from strings.
+/// --before, --older-than, --after and --keep-anniversaries are built from these.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    /// created before this instant.
+    CreatedBefore(DateTime<Utc>),
+    /// created at or after this instant.
+    CreatedSince(DateTime<Utc>),
+    /// older than this at the time the filter is checked.
+    AgeOlderThan(chrono::Duration),
+    /// made on this month and day in any year.
+    PostedOn(u32, u32),
+    /// at least this many likes.
+    MinLikes(u64),
+    /// at least this many retweets.
+    MinRetweets(u64),
+    /// text matches somewhere. the text is unescaped and made [`text::comparable`] first, with
+    /// these --ignore-case and --strip-entities.
+    TextMatches { regex: Regex, ignore_case: bool, strip_entities: bool },
+    /// a retweet, reply or original post.
+    Kind(PostKind),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+fn created_at(tweet: &Value) -> Option<DateTime<Utc>> {
+    let created_at = tweet["tweet"]["created_at"].as_str()?;
+    DateTime::parse_from_str(created_at, "%a %b %d %H:%M:%S %z %Y").ok().map(|created_at| created_at.to_utc())
+}
+
+fn count(tweet: &Value, key: &str) -> u64 {
+    let value = &tweet["tweet"][key];
+    value.as_str().and_then(|count| count.parse().ok()).or_else(|| value.as_u64()).unwrap_or(0)
+}
+
+// not every constructor has a flag. the rest are for applications embedding the library.
+impl Filter {
+    pub fn created_before(instant: DateTime<Utc>) -> Self {
+        Filter::CreatedBefore(instant)
+    }
+
+    pub fn created_since(instant: DateTime<Utc>) -> Self {
+        Filter::CreatedSince(instant)
+    }
+
+    pub fn age_older_than(age: chrono::Duration) -> Self {
+        Filter::AgeOlderThan(age)
+    }
+
+    pub fn posted_on(month: u32, day: u32) -> Self {
+        Filter::PostedOn(month, day)
+    }
+
+    pub fn min_likes(likes: u64) -> Self {
+        Filter::MinLikes(likes)
+    }
+
+    pub fn min_retweets(retweets: u64) -> Self {
+        Filter::MinRetweets(retweets)
+    }
+
+    pub fn text_matches(regex: Regex) -> Self {
+        Filter::text_matches_with(regex, false, false)
+    }
+
+    /// `regex` should come from [`text::comparable_pattern`] with the same `ignore_case`.
+    pub fn text_matches_with(regex: Regex, ignore_case: bool, strip_entities: bool) -> Self {
+        Filter::TextMatches { regex, ignore_case, strip_entities }
+    }
+
+    pub fn kind(kind: PostKind) -> Self {
+        Filter::Kind(kind)
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    /// whether `tweet`, an entry of the archive, passes. `now` is what ages are measured from.
+    /// an entry without a readable created_at fails every time condition.
+    pub fn matches(&self, tweet: &Value, now: DateTime<Utc>) -> bool {
+        match self {
+            Filter::CreatedBefore(instant) => created_at(tweet).is_some_and(|created_at| created_at < *instant),
+            Filter::CreatedSince(instant) => created_at(tweet).is_some_and(|created_at| created_at >= *instant),
+            Filter::AgeOlderThan(age) => created_at(tweet).is_some_and(|created_at| now - created_at > *age),
+            Filter::PostedOn(month, day) => created_at(tweet).is_some_and(|created_at| (created_at.month(), created_at.day()) == (*month, *day)),
+            Filter::MinLikes(likes) => count(tweet, "favorite_count") >= *likes,
+            Filter::MinRetweets(retweets) => count(tweet, "retweet_count") >= *retweets,
+            Filter::TextMatches { regex, ignore_case, strip_entities } => {
+                regex.is_match(&text::comparable(&text::unescape_html(text::of(&tweet["tweet"])), *ignore_case, *strip_entities))
+            },
+            Filter::Kind(kind) => PostKind::of(tweet) == *kind,
+            Filter::And(left, right) => left.matches(tweet, now) && right.matches(tweet, now),
+            Filter::Or(left, right) => left.matches(tweet, now) || right.matches(tweet, now),
+            Filter::Not(filter) => !filter.matches(tweet, now),
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
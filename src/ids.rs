@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::thread;
+
+const HOSTS: [&str; 5] = ["x.com", "twitter.com", "www.x.com", "www.twitter.com", "mobile.twitter.com"];
+
+/// a bare id, or a post url such as https://x.com/user/status/123?s=20 or https://twitter.com/i/web/status/123.
+fn parse_line(line: &str) -> Option<u64> {
+    if line.bytes().all(|byte| byte.is_ascii_digit()) {
+        return line.parse().ok();
+    }
+    let url = Url::parse(line).ok()?;
+    if !HOSTS.contains(&url.host_str()?) {
+        return None;
+    }
+    let mut segments = url.path_segments()?;
+    segments.find(|segment| *segment == "status" || *segment == "statuses")?;
+    segments.next()?.parse().ok()
+}
+
+/// the ids listed in `path`, one post per line. blank lines and lines starting with `#` are skipped.
+/// every line that is neither is reported, and any of them fails the whole file.
+pub fn read(path: &Path) -> Result<Vec<u64>> {
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read ids file. path={}", path.display()))?;
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    let mut invalid = 0;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Some(id) => {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            },
+            None => {
+                eprintln!("invalid ids file line. path={} line={} value={}", path.display(), index + 1, line);
+                invalid += 1;
+            },
+        }
+    }
+    anyhow::ensure!(invalid == 0, "ids file has lines that are neither an id nor a post url. path={} invalid={}", path.display(), invalid);
+    Ok(ids)
+}
+
+/// the archive entries of `ids`, in the order of the archive. ids the archive doesn't have get a
+/// bare entry, since deleting only needs the id.
+pub fn select(entries: &[Value], ids: &[u64]) -> Vec<Value> {
+    let wanted: HashSet<u64> = ids.iter().copied().collect();
+    let mut selected: Vec<Value> = entries.iter().filter(|tweet| thread::id(tweet).is_some_and(|id| wanted.contains(&id))).cloned().collect();
+    let found: HashSet<u64> = selected.iter().filter_map(thread::id).collect();
+    let missing: Vec<u64> = ids.iter().copied().filter(|id| !found.contains(id)).collect();
+    if !missing.is_empty() {
+        eprintln!("{} listed posts not in the archive are deleted by id alone. posts={}", crate::color::warning(), missing.len());
+    }
+    selected.extend(missing.into_iter().map(|id| json!({ "tweet": { "id": id.to_string(), "id_str": id.to_string() } })));
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_and_post_urls() {
+        assert_eq!(parse_line("1234567890"), Some(1234567890));
+        assert_eq!(parse_line("https://x.com/user/status/123?s=20"), Some(123));
+        assert_eq!(parse_line("https://twitter.com/i/web/status/456"), Some(456));
+        assert_eq!(parse_line("https://mobile.twitter.com/user/statuses/789"), Some(789));
+    }
+
+    #[test]
+    fn rejects_everything_else() {
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("12a"), None);
+        assert_eq!(parse_line("https://example.com/user/status/123"), None);
+        assert_eq!(parse_line("https://x.com/user"), None);
+        assert_eq!(parse_line("https://x.com/user/status/latest"), None);
+    }
+}
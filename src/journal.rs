@@ -0,0 +1,107 @@
+use serde_json::Value;
+use std::{collections::HashSet, fs::{self, File, OpenOptions}, io::Write, path::{Path, PathBuf}};
+
+use crate::thread;
+
+/// journal entries after which the remaining file is rewritten and the journal started over.
+pub const COMPACT_EVERY: usize = 1000;
+
+/// `remaining.json` -> `remaining.json.journal`
+fn path_for(state: &Path) -> PathBuf {
+    let mut path = state.as_os_str().to_owned();
+    path.push(".journal");
+    PathBuf::from(path)
+}
+
+/// ids of the posts that left the remaining file since it was last written, one per line.
+/// appending a line is cheap enough to do after every deletion; the whole file is only rewritten
+/// every `COMPACT_EVERY` posts and at exit.
+pub struct Journal {
+    path: PathBuf,
+    file: Option<File>,
+    entries: usize,
+}
+
+impl Journal {
+    pub fn new(state: &Path) -> Self {
+        Self { path: path_for(state), file: None, entries: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    pub fn record(&mut self, id: u64) {
+        if self.file.is_none() {
+            match OpenOptions::new().create(true).append(true).open(&self.path) {
+                Ok(file) => self.file = Some(file),
+                Err(err) => {
+                    eprintln!("failed to open {}. err={}", self.path.display(), err);
+                    return;
+                },
+            }
+        }
+        let file = self.file.as_mut().expect("opened above.");
+        match writeln!(file, "{}", id).and_then(|_| file.sync_data()) {
+            Ok(()) => self.entries += 1,
+            Err(err) => eprintln!("failed to write {}. err={}", self.path.display(), err),
+        }
+    }
+
+    /// the remaining file was just rewritten, so everything journaled is in it.
+    pub fn clear(&mut self) {
+        self.file = None;
+        self.entries = 0;
+        if let Err(err) = fs::remove_file(&self.path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("failed to remove {}. err={}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+/// drops the posts a crashed or killed run journaled from `entries` of the remaining file at `state`.
+pub fn replay(state: &Path, entries: &mut Vec<Value>) {
+    let Ok(journal) = fs::read_to_string(path_for(state)) else {
+        return;
+    };
+    // a torn last line has no newline yet, and may be the start of another id.
+    let done: HashSet<u64> = journal.split_inclusive('\n')
+        .filter(|line| line.ends_with('\n'))
+        .filter_map(|line| line.trim().parse().ok())
+        .collect();
+    let before = entries.len();
+    entries.retain(|tweet| !thread::id(tweet).is_some_and(|id| done.contains(&id)));
+    println!("replayed journal. path={} removed={}", path_for(state).display(), before - entries.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post(id: u64) -> Value {
+        json!({ "tweet": { "id": id.to_string() } })
+    }
+
+    #[test]
+    fn replays_until_compacted() {
+        let state = std::env::temp_dir().join(format!("post_remove-journal-{}.json", std::process::id()));
+        let mut journal = Journal::new(&state);
+        journal.record(1);
+        journal.record(3);
+        assert_eq!(journal.len(), 2);
+        // a run killed while writing 42.
+        OpenOptions::new().append(true).open(path_for(&state)).unwrap().write_all(b"4").unwrap();
+        let mut entries = vec![post(1), post(2), post(3), post(4)];
+        replay(&state, &mut entries);
+        assert_eq!(entries.iter().filter_map(thread::id).collect::<Vec<_>>(), [2, 4]);
+
+        journal.clear();
+        assert_eq!(journal.len(), 0);
+        assert!(!path_for(&state).exists());
+        let mut entries = vec![post(1)];
+        replay(&state, &mut entries);
+        assert_eq!(entries.len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs::{self, File, OpenOptions}, io::Write, path::{Path, PathBuf}};
+
+/// one line of the deleted-posts ledger.
+#[derive(Serialize, Deserialize)]
+pub struct LedgerRecord {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub id: u64,
+    pub created_at: String,
+    pub processed_at: DateTime<Utc>,
+    pub outcome: String,
+    pub text: String,
+    /// `text` is a hash and length from --redact-text.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+    pub favorite_count: u64,
+    pub retweet_count: u64,
+    /// the run that processed the post. lines written before run ids existed have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+impl LedgerRecord {
+    /// `processed_at` comes from the run's clock, so a simulated run writes simulated times.
+    pub fn new(id: u64, tweet: &Value, outcome: &str, run_id: &str, processed_at: DateTime<Utc>) -> Self {
+        let count = |key: &str| tweet[key].as_str().and_then(|count| count.parse().ok()).unwrap_or(0);
+        Self {
+            schema_version: crate::schema::LEDGER,
+            id,
+            created_at: tweet["created_at"].as_str().unwrap_or_default().to_string(),
+            processed_at,
+            outcome: outcome.to_string(),
+            text: crate::text::of(tweet).to_string(),
+            redacted: false,
+            favorite_count: count("favorite_count"),
+            retweet_count: count("retweet_count"),
+            run_id: Some(run_id.to_string()),
+        }
+    }
+
+    pub fn redact(&mut self) {
+        if !self.redacted {
+            self.text = crate::text::redact(&self.text);
+            self.redacted = true;
+        }
+    }
+}
+
+/// rotation policy shared by every append-only file the tool writes.
+#[derive(Clone, Copy)]
+pub struct Rotation {
+    pub max_size: Option<u64>,
+    pub keep: usize,
+}
+
+impl Rotation {
+    /// shifts `path` to `path.1`, `path.1` to `path.2` and so on once it exceeds `max_size`.
+    /// archives beyond `keep` are removed.
+    pub fn rotate_if_needed(&self, path: &Path) -> Result<()> {
+        let Some(max_size) = self.max_size else {
+            return Ok(());
+        };
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(()),
+        };
+        if size < max_size {
+            return Ok(());
+        }
+        let archive = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+        if self.keep == 0 {
+            fs::remove_file(path)?;
+            return Ok(());
+        }
+        let _ = fs::remove_file(archive(self.keep));
+        for n in (1..self.keep).rev() {
+            if archive(n).exists() {
+                fs::rename(archive(n), archive(n + 1))?;
+            }
+        }
+        fs::rename(path, archive(1))?;
+        Ok(())
+    }
+}
+
+pub struct Ledger {
+    path: PathBuf,
+    rotation: Rotation,
+    redact_text: bool,
+}
+
+impl Ledger {
+    pub fn new(path: PathBuf, rotation: Rotation, redact_text: bool) -> Self {
+        Self { path, rotation, redact_text }
+    }
+
+    pub fn append(&self, mut record: LedgerRecord) -> Result<()> {
+        if self.redact_text {
+            record.redact();
+        }
+        self.rotation.rotate_if_needed(&self.path)?;
+        let mut file: File = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,897 @@
+//! deletes posts of an X archive. the `post_remove` binary is `cli_main`; applications that want
+//! the deletions without the command line load an archive with `ArchiveLoader`, pick posts with
+//! `TweetFilter` and hand them to a `DeletionEngine` set up with `DeletionEngine::builder`. the
+//! command line runs on the same engine.
+
+#[cfg(not(any(feature = "x-v1", feature = "x-v2")))]
+compile_error!("post_remove needs an API to delete with. enable x-v1, x-v2 or both.");
+
+use anyhow::{Context, Ok, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use clap::{CommandFactory, FromArgMatches, ValueEnum};
+use dotenv::dotenv;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{borrow::Cow, collections::{BTreeMap, HashSet}, env, fs::File, io::{self, BufRead, BufReader, IsTerminal, Write}, path::{Path, PathBuf}, sync::Arc};
+use oauth1::{Token, authorize};
+
+mod api;
+mod approval;
+mod audit;
+mod archive;
+mod auth;
+mod backup;
+mod bar;
+#[cfg(feature = "x-v2")]
+mod bookmarks;
+mod cli;
+mod color;
+mod clock;
+mod config;
+mod decay;
+mod dm;
+mod doctor;
+mod duplicates;
+mod duration;
+mod engine;
+mod error;
+mod events;
+mod expiry;
+mod export;
+mod filter;
+mod ids;
+mod journal;
+mod ledger;
+mod likes;
+mod links;
+mod log;
+mod manual;
+mod nuke;
+#[cfg(feature = "x-v2")]
+mod oauth2;
+mod pace;
+mod lint;
+mod plan;
+mod progress;
+mod quota;
+mod record;
+mod rehost;
+mod replay;
+mod schedule;
+mod schema;
+mod signing;
+mod spill;
+mod sqlite;
+mod status;
+mod summary;
+mod report;
+mod retry;
+mod restore;
+mod systemd;
+mod text;
+mod thread;
+mod timezone;
+mod version;
+mod web;
+
+use clock::{SharedClock, SimulatedClock, SystemClock};
+use cli::{BackupCommand, BatchBy, Cli, Command, ExportCommand, RestoreCommand, RunOptions, Selection};
+use engine::run;
+use filter::Filter;
+use ledger::{LedgerRecord, Rotation};
+
+pub use engine::{ArchiveLoader, DeletionEngine, EngineBuilder};
+pub use error::{exit_code, Error};
+pub use filter::{Filter as TweetFilter, PostKind};
+
+/// written at the top of the remaining file so later runs can tell which policy produced it.
+#[derive(Serialize, Deserialize)]
+struct RunMetadata {
+    #[serde(default)]
+    schema_version: u32,
+    tool_version: String,
+    run_at: DateTime<Utc>,
+    /// identifies the run in its output, ledger lines and progress file.
+    #[serde(default)]
+    run_id: String,
+    cutoff: Option<String>,
+    filters: Vec<String>,
+    total: usize,
+    candidates: usize,
+    processed: usize,
+    remaining: usize,
+    #[serde(default)]
+    failed: usize,
+    /// the authenticated user the file was created for. a run as anyone else is refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account_id: Option<String>,
+    /// posts left per part of a multi-part archive.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    parts: BTreeMap<String, usize>,
+    /// a plan written with --require-approval. kept while the plan is applied, so a resumed apply checks too.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    requires_approval: bool,
+    /// what happened to each post processed so far, by id, across resumed runs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    outcomes: BTreeMap<u64, String>,
+}
+
+impl RunMetadata {
+    /// carries over from the run that wrote the state file what outlives a single run.
+    fn resume(&mut self, previous: Option<RunMetadata>) {
+        if let Some(previous) = previous {
+            // the remaining file stays bound to the account that started it.
+            self.account_id = previous.account_id;
+            self.outcomes = previous.outcomes;
+        }
+    }
+}
+
+/// `path` + `suffix`, e.g. `remaining.json.tmp`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// copies `path` to `path.bak` before a run first rewrites it, unless an earlier run already did.
+/// the first copy is the one worth keeping.
+fn save_original(path: &Path) -> Result<()> {
+    let backup = with_suffix(path, ".bak");
+    if path.exists() && !backup.exists() {
+        std::fs::copy(path, &backup).with_context(|| format!("failed to copy to {}.", backup.display()))?;
+        println!("saved original. path={}", backup.display());
+    }
+    Ok(())
+}
+
+/// writes to a temporary file next to `path` and renames it over `path`, so a crash mid-write
+/// leaves the previous version in place instead of a truncated file.
+fn write_atomically(path: &Path, value: &Value) -> Result<()> {
+    let temporary = with_suffix(path, ".tmp");
+    let file = File::create(&temporary).with_context(|| format!("failed to create {}.", temporary.display()))?;
+    let mut writer = io::BufWriter::new(file);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    std::fs::rename(&temporary, path).with_context(|| format!("failed to rename {}.", temporary.display()))?;
+    Ok(())
+}
+
+/// asks the user on stdin. prompts are never shown when stdin isn't a terminal (cron etc.).
+fn confirm(options: &RunOptions, message: &str) -> Result<bool> {
+    if options.yes {
+        return Ok(true);
+    }
+    if options.non_interactive || !io::stdin().is_terminal() {
+        anyhow::bail!("confirmation required in non-interactive mode. pass --yes to accept. prompt={}", message);
+    }
+    print!("{} [y/N] ", message);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// like `confirm`, but the answer has to be `phrase` typed out. --yes doesn't skip it; without a terminal
+/// only `accepted`, a flag dedicated to the one prompt, does.
+fn confirm_phrase(options: &RunOptions, phrase: &str, accepted: bool) -> Result<bool> {
+    if options.non_interactive || !io::stdin().is_terminal() {
+        anyhow::ensure!(accepted, "typed confirmation required and there is no terminal to type it on. pass --yes-delete-everything to accept. prompt={}", phrase);
+        return Ok(true);
+    }
+    print!("type \"{}\" to confirm: ", phrase);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim() == phrase)
+}
+
+/// none when created_at is missing or not in the archive's format (%a %b %d %H:%M:%S %z %Y).
+fn post_time(tweet: &Value) -> Option<DateTime<Utc>> {
+    let post_created_at = tweet["tweet"]["created_at"].as_str()?;
+    DateTime::parse_from_str(post_created_at, "%a %b %d %H:%M:%S %z %Y").ok().map(|created_at| created_at.to_utc())
+}
+
+/// `%Y-%m-%d` means midnight UTC of that day. an RFC 3339 timestamp gives an exact instant.
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    if let std::result::Result::Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.to_utc())
+}
+
+/// splits posts into ordered batches. without `batch_by` everything is one batch.
+fn make_batches(posts: Vec<Value>, batch_by: Option<BatchBy>) -> Vec<(String, Vec<Value>)> {
+    match batch_by {
+        None => vec![("all".to_string(), posts)],
+        Some(BatchBy::Month) => {
+            let mut batches: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+            for tweet in posts {
+                // sorted after every month, so they go first.
+                let month = post_time(&tweet).map_or_else(|| "unknown".to_string(), |created_at| created_at.format("%Y-%m").to_string());
+                batches.entry(month).or_default().push(tweet);
+            }
+            // newest month first. replies are newer than their parents, so this keeps them ahead.
+            batches.into_iter().rev().collect()
+        },
+        Some(BatchBy::Conversation) => {
+            let mut batches: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+            for tweet in posts {
+                let conversation = tweet["dm"]["conversationId"].as_str().unwrap_or("none").to_string();
+                batches.entry(conversation).or_default().push(tweet);
+            }
+            batches.into_iter().collect()
+        },
+    }
+}
+
+/// `today`, `%m-%d` or `%Y-%m-%d` as (month, day).
+fn parse_month_day(value: &str, now: DateTime<Utc>) -> Option<(u32, u32)> {
+    if value == "today" {
+        let today = now;
+        return Some((today.month(), today.day()));
+    }
+    // a leap year, so 02-29 parses too.
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("2000-{}", value), "%Y-%m-%d"))
+        .ok()?;
+    Some((date.month(), date.day()))
+}
+
+fn get_tweets_data(path: &Path) -> Result<serde_json::Value> {
+    let file = File::open(path).with_context(|| format!("failed to open file. path={}", path.display()))?;
+    parse_tweets_data(BufReader::new(file)).map_err(|err| Error::parse(path, err).into())
+}
+
+/// plain JSON, or tweets.js of the archive as downloaded: `window.YTD.tweets.part0 = [...]`.
+fn parse_tweets_data<R: io::Read>(mut reader: BufReader<R>) -> std::result::Result<serde_json::Value, serde_json::Error> {
+    let head = reader.fill_buf().map_err(serde_json::Error::io)?;
+    let bom = if head.starts_with(b"\xEF\xBB\xBF") { 3 } else { 0 };
+    if !head[bom..].trim_ascii_start().starts_with(b"window.") {
+        return serde_json::from_reader(reader);
+    }
+    // skip the assignment. whatever follows the value, such as a `;`, is ignored.
+    reader.read_until(b'=', &mut Vec::new()).map_err(serde_json::Error::io)?;
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    serde_json::Value::deserialize(&mut deserializer)
+}
+
+async fn delete_tweet(method: reqwest::Method, url: &str, credentials: &Credentials) -> Result<Response, reqwest::Error> {
+    let client = reqwest::Client::new();
+
+    let consumer = Token::new(&credentials.consumer_key, &credentials.consumer_secret);
+    let access = Token::new(&credentials.access_key, &credentials.access_secret);
+    // query parameters are signed as parameters, the base string takes the url without them.
+    let base_url = url.split_once('?').map_or(url, |(base_url, _)| base_url);
+    let params: Vec<(String, String)> = reqwest::Url::parse(url).map(|url| url.query_pairs().into_owned().collect()).unwrap_or_default();
+    let signed = params.iter().map(|(key, value)| (key.as_str(), Cow::from(value.as_str()))).collect();
+    let authorize_header = authorize(method.as_str(), base_url, &consumer, Some(&access), Some(signed));
+    let response = client
+        .request(method.clone(), url)
+        .header("Authorization", &authorize_header)
+        .send()
+        .await?;
+    // rate limits and missing posts say nothing about the signature.
+    if let Some(debug) = credentials.debug_auth.as_ref().filter(|_| !response.status().is_success() && ![404, 429].contains(&response.status().as_u16())) {
+        let params: Vec<(&str, &str)> = params.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        debug.rejected(credentials, method.as_str(), base_url, &authorize_header, &params, response.status().as_u16());
+    }
+    std::result::Result::Ok(response)
+}
+
+/// OAuth 1.0a user context keys from the environment (.env).
+struct Credentials {
+    consumer_key: String,
+    consumer_secret: String,
+    access_key: String,
+    access_secret: String,
+    /// --debug-auth. set by the run, off everywhere else.
+    debug_auth: Option<signing::DebugAuth>,
+}
+
+impl Credentials {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            consumer_key: env::var("CONSUMER_KEY").context("CONSUMER_KEY not found in environment.")?,
+            consumer_secret: env::var("CONSUMER_SECRET").context("CONSUMER_SECRET not found in environment.")?,
+            access_key: env::var("ACCESS_KEY").context("ACCESS_KEY not found in environment.")?,
+            access_secret: env::var("ACCESS_SECRET").context("ACCESS_SECRET not found in environment.")?,
+            debug_auth: None,
+        })
+    }
+}
+
+/// the user the credentials belong to.
+struct Account {
+    id: String,
+    screen_name: String,
+    /// x-access-level of the app, e.g. "read-write". deleting needs write access.
+    access_level: Option<String>,
+}
+
+async fn verify_credentials(credentials: &Credentials) -> Result<Account> {
+    let url = "https://api.x.com/1.1/account/verify_credentials.json";
+    let consumer = Token::new(&credentials.consumer_key, &credentials.consumer_secret);
+    let access = Token::new(&credentials.access_key, &credentials.access_secret);
+    let authorize_header = authorize("GET", url, &consumer, Some(&access), None);
+    let response = reqwest::Client::new()
+        .get(url)
+        .header("Authorization", &authorize_header)
+        .send()
+        .await?;
+    if let Some(debug) = credentials.debug_auth.as_ref().filter(|_| !response.status().is_success()) {
+        debug.rejected(credentials, "GET", url, &authorize_header, &[], response.status().as_u16());
+    }
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(Error::Unauthorized { status: 401 }).context("failed to verify credentials.");
+    }
+    let response = response
+        .error_for_status()
+        .context("failed to verify credentials.")?;
+    let access_level = response.headers().get("x-access-level").and_then(|level| level.to_str().ok()).map(str::to_string);
+    let user: Value = response.json().await?;
+    let id = user["id_str"].as_str().context("verify_credentials returned no id_str.")?;
+    Ok(Account { id: id.to_string(), screen_name: user["screen_name"].as_str().unwrap_or_default().to_string(), access_level })
+}
+
+/// `h:mm:ss`
+fn format_remaining(duration: tokio::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// the entries of an archive or remaining file, and the metadata of the run that wrote the latter.
+fn get_state(path: &Path) -> Result<(Vec<Value>, Option<RunMetadata>)> {
+    let tweets = get_tweets_data(path)?;
+    // a remaining file written by a previous run wraps the array with its metadata.
+    let (mut entries, metadata) = match tweets {
+        Value::Array(entries) => (entries, None),
+        Value::Object(mut object) => match object.remove("tweets") {
+            Some(Value::Array(entries)) => {
+                let metadata = match object.remove("metadata") {
+                    Some(mut metadata) => {
+                        schema::migrate_metadata(&mut metadata).with_context(|| format!("unsupported state file. path={}", path.display()))?;
+                        serde_json::from_value(metadata).ok()
+                    },
+                    None => None,
+                };
+                (entries, metadata)
+            },
+            _ => return Err(Error::parse(path, "expect a list of posts or a remaining file.").into()),
+        },
+        _ => return Err(Error::parse(path, "expect a list of posts or a remaining file.").into()),
+    };
+    journal::replay(path, &mut entries);
+    Ok((entries, metadata))
+}
+
+/// `tweets.js` -> `tweets.js.state.json`. a run keeps what's left of an input there, and never rewrites the input.
+fn state_path_for(input: &Path) -> PathBuf {
+    with_suffix(input, ".state.json")
+}
+
+/// `get_state` of a single file, and where its run keeps its state. a state file is its own.
+fn get_file_input(path: &Path) -> Result<(Vec<Value>, Option<RunMetadata>, PathBuf)> {
+    let (mut entries, metadata) = get_state(path)?;
+    if metadata.is_some() {
+        return Ok((entries, metadata, path.to_path_buf()));
+    }
+    let state_path = state_path_for(path);
+    // the journal of a run that died before it first wrote the state file.
+    journal::replay(&state_path, &mut entries);
+    Ok((entries, metadata, state_path))
+}
+
+/// with --resume, what the previous run left in the state file of `input` instead of the input.
+/// without it, an existing state file is refused, so a new run never quietly picks up an old one.
+fn resume_state(input: (Vec<Value>, Option<RunMetadata>, PathBuf), options: &RunOptions) -> Result<(Vec<Value>, Option<RunMetadata>, PathBuf)> {
+    let (entries, metadata, state_path) = input;
+    if metadata.is_some() {
+        // a state file passed as the input resumes anyway.
+        return Ok((entries, metadata, state_path));
+    }
+    let state_path = match &options.workdir {
+        Some(Some(workdir)) => workdir.join("remaining.json"),
+        _ => state_path,
+    };
+    if !state_path.exists() {
+        if options.resume {
+            eprintln!("{} nothing to resume. starting from the input. path={}", color::warning(), state_path.display());
+        }
+        return Ok((entries, None, state_path));
+    }
+    anyhow::ensure!(options.resume, "a previous run left its state. pass --resume to continue it, or remove it to start over. path={}", state_path.display());
+    let (entries, metadata) = get_state(&state_path)?;
+    println!("resuming. path={} posts={} processed={}",
+        state_path.display(), entries.len(), metadata.as_ref().map_or(0, |metadata| metadata.outcomes.len()));
+    Ok((entries, metadata, state_path))
+}
+
+/// like `get_file_input`, but `path` may also be an archive directory, a glob over tweets-partN.js or
+/// the downloaded zip. entries of a multi-part input remember their part, and the remaining file
+/// goes next to the parts, or next to the zip.
+fn get_input(path: &Path) -> Result<(Vec<Value>, Option<RunMetadata>, PathBuf)> {
+    let path: &Path = &archive::long_path(path);
+    let (parts, state_path) = if archive::is_zip(path) {
+        let state_path = path.with_extension("remaining.json");
+        println!("reading the archive zip. the remaining file goes to {}.", state_path.display());
+        (archive::zip_parts(path)?, state_path)
+    } else {
+        let parts = archive::parts(path)?;
+        if let [single] = parts.as_slice() {
+            let (mut entries, metadata, state_path) = get_file_input(single)?;
+            archive::normalize(&mut entries, single)?;
+            return Ok((entries, metadata, state_path));
+        }
+        let state_path = parts[0].with_file_name("remaining.json");
+        let parts = parts.iter()
+            .map(|part| Ok((part.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(), get_state(part)?.0)))
+            .collect::<Result<_>>()?;
+        (parts, state_path)
+    };
+    let multi_part = parts.len() > 1;
+    let mut entries = Vec::new();
+    for (name, mut part_entries) in parts {
+        archive::normalize(&mut part_entries, Path::new(&name))?;
+        println!("loaded. part={} posts={}", name, part_entries.len());
+        entries.extend(part_entries.into_iter().map(|mut entry| {
+            if multi_part {
+                entry["part"] = Value::String(name.clone());
+            }
+            entry
+        }));
+    }
+    // the journal of a run that died before it first wrote the remaining file.
+    journal::replay(&state_path, &mut entries);
+    Ok((entries, None, state_path))
+}
+
+fn get_entries(path: &Path) -> Result<Vec<Value>> {
+    Ok(get_input(path)?.0)
+}
+
+/// why an archive entry isn't a candidate. reasons are stable codes for downstream tools:
+/// not-listed, not-in-thread, not-duplicate, min-age, keep-ids, skip-ids, filter:sql, filter:match, exclude, filter:only, kind, anniversary, engagement, conversation,
+/// decay, no-expiry, not-expired, filter:max-length, links-alive and not-before.
+#[derive(Serialize)]
+struct Skip {
+    id: u64,
+    reason: &'static str,
+}
+
+struct Selected {
+    posts: Vec<Value>,
+    filters: Vec<String>,
+    skipped: Vec<Skip>,
+}
+
+/// a --match or --exclude pattern, compiled to match [`text::comparable`] text.
+fn text_regex(pattern: &str, ignore_case: bool) -> std::result::Result<regex::Regex, regex::Error> {
+    regex::RegexBuilder::new(&text::comparable_pattern(pattern, ignore_case)).case_insensitive(ignore_case).build()
+}
+
+/// moves the posts `keep` rejects from `posts` to `skipped` with `reason`.
+fn skip_unless(posts: &mut Vec<Value>, skipped: &mut Vec<Skip>, reason: &'static str, mut keep: impl FnMut(&Value) -> bool) {
+    let (kept, rejected): (Vec<Value>, Vec<Value>) = posts.drain(..).partition(|tweet| keep(tweet));
+    skipped.extend(rejected.iter().filter_map(thread::id).map(|id| Skip { id, reason }));
+    *posts = kept;
+}
+
+/// records every post of `before` missing from `after` as skipped with `reason`.
+fn skip_missing(before: &[Value], after: &[Value], skipped: &mut Vec<Skip>, reason: &'static str) {
+    let kept: HashSet<u64> = after.iter().filter_map(thread::id).collect();
+    skipped.extend(before.iter().filter_map(thread::id).filter(|id| !kept.contains(id)).map(|id| Skip { id, reason }));
+}
+
+/// applies the selection flags to the archive. returns the candidates, a description of the filters
+/// and the reason every other post was left out.
+/// `pending` keeps posts whose --expiry hasn't passed yet, for --daemon. age based filters measure from `now`.
+async fn select(selection: &Selection, entries: &[Value], pending: bool, now: DateTime<Utc>) -> Result<Selected> {
+    let time = selection.cutoff()
+        .map(|cutoff| parse_instant(cutoff).with_context(|| format!("failed time parse. (format %Y-%m-%d or RFC 3339) value={}", cutoff)))
+        .transpose()?;
+    if let Some(time) = time.filter(|time| *time > now && selection.ids_file.is_none()) {
+        // almost always a typo in the year.
+        if !selection.force {
+            anyhow::bail!("cutoff {} is in the future and would select the entire account. pass --force if this is intended.", time);
+        }
+        eprintln!("{} cutoff {} is in the future. every post of the account is selected.", color::warning(), time);
+    }
+    for warning in lint::check(selection, now) {
+        eprintln!("{} {}", color::warning(), warning);
+    }
+    let mut filters = Vec::new();
+    let mut skipped = Vec::new();
+    let mut filtered_data: Vec<serde_json::Value> = if let Some(path) = &selection.ids_file {
+        let ids = ids::read(path)?;
+        filters.push(format!("ids listed in {}", path.display()));
+        let listed = ids::select(entries, &ids);
+        skip_missing(entries, &listed, &mut skipped, "not-listed");
+        listed
+    } else if let Some(root) = selection.delete_thread {
+        filters.push(format!("thread rooted at {}", root));
+        let thread = thread::thread(entries, root).with_context(|| format!("post not found in archive. id={}", root))?;
+        skip_missing(entries, &thread, &mut skipped, "not-in-thread");
+        thread
+    } else if selection.dedupe {
+        filters.push(format!("duplicates (keep {})", selection.keep_copy.to_possible_value().expect("no skipped variants").get_name()));
+        let duplicates = duplicates::find(entries, selection.keep_copy, selection.ignore_case, selection.strip_entities);
+        skip_missing(entries, &duplicates, &mut skipped, "not-duplicate");
+        duplicates
+    } else {
+        entries.to_vec()
+    };
+    let older = time.map(Filter::created_before).or(selection.older_than.map(Filter::age_older_than));
+    if let Some(older) = older.filter(|_| selection.ids_file.is_none()) {
+        skip_unless(&mut filtered_data, &mut skipped, "min-age", |tweet| older.matches(tweet, now));
+    }
+    if let Some(path) = &selection.keep_ids {
+        let kept: HashSet<u64> = ids::read(path)?.into_iter().collect();
+        filters.push(format!("keep ids listed in {}", path.display()));
+        let before = filtered_data.len();
+        skip_unless(&mut filtered_data, &mut skipped, "keep-ids", |tweet| !thread::id(tweet).is_some_and(|id| kept.contains(&id)));
+        println!("protected posts. path={} listed={} protected={}", path.display(), kept.len(), before - filtered_data.len());
+    }
+    if let Some(path) = &selection.skip_ids_file {
+        let skipped_ids: HashSet<u64> = ids::read(path)?.into_iter().collect();
+        filters.push(format!("skip ids listed in {}", path.display()));
+        let before = filtered_data.len();
+        skip_unless(&mut filtered_data, &mut skipped, "skip-ids", |tweet| !thread::id(tweet).is_some_and(|id| skipped_ids.contains(&id)));
+        println!("skipped posts for this run. path={} listed={} skipped={}", path.display(), skipped_ids.len(), before - filtered_data.len());
+    }
+    if let Some(query) = &selection.select_sql {
+        let ids = sqlite::select_ids(query, selection.select_db.as_deref(), entries)?;
+        filters.push(format!("sql: {}", query));
+        skip_unless(&mut filtered_data, &mut skipped, "filter:sql", |tweet| thread::id(tweet).is_some_and(|id| ids.contains(&id)));
+    }
+    if let Some(pattern) = &selection.match_text {
+        let regex = text_regex(pattern, selection.ignore_case).with_context(|| format!("invalid --match regex. regex={}", pattern))?;
+        filters.push(format!("text matches {}", pattern));
+        let matching = Filter::text_matches_with(regex, selection.ignore_case, selection.strip_entities);
+        skip_unless(&mut filtered_data, &mut skipped, "filter:match", |tweet| matching.matches(tweet, now));
+    }
+    if let Some(pattern) = &selection.exclude {
+        let regex = text_regex(pattern, selection.ignore_case).with_context(|| format!("invalid --exclude regex. regex={}", pattern))?;
+        filters.push(format!("text doesn't match {}", pattern));
+        let not_excluded = !Filter::text_matches_with(regex, selection.ignore_case, selection.strip_entities);
+        skip_unless(&mut filtered_data, &mut skipped, "exclude", |tweet| not_excluded.matches(tweet, now));
+    }
+    let kinds = |kinds: &[PostKind]| kinds.iter().map(|kind| kind.to_possible_value().expect("no skipped variants").get_name().to_string()).collect::<Vec<_>>().join(", ");
+    if let Some(only) = selection.only.iter().copied().map(Filter::kind).reduce(Filter::or) {
+        filters.push(format!("only {}", kinds(&selection.only)));
+        skip_unless(&mut filtered_data, &mut skipped, "filter:only", |tweet| only.matches(tweet, now));
+    }
+    if let Some(kept) = selection.keep.iter().copied().map(Filter::kind).reduce(Filter::or) {
+        filters.push(format!("keep {}", kinds(&selection.keep)));
+        let not_kept = !kept;
+        skip_unless(&mut filtered_data, &mut skipped, "kind", |tweet| not_kept.matches(tweet, now));
+    }
+    if let Some(anniversary) = &selection.keep_anniversaries {
+        let (month, day) = parse_month_day(anniversary, now).with_context(|| format!("failed --keep-anniversaries parse. (format %m-%d or %Y-%m-%d) value={}", anniversary))?;
+        filters.push(format!("keep posts made on {:02}-{:02}", month, day));
+        let ordinary_day = !Filter::posted_on(month, day);
+        skip_unless(&mut filtered_data, &mut skipped, "anniversary", |tweet| ordinary_day.matches(tweet, now));
+    }
+    let engaged = [selection.min_favs.map(Filter::min_likes), selection.min_retweets.map(Filter::min_retweets)]
+        .into_iter()
+        .flatten()
+        .reduce(Filter::or);
+    if let Some(engaged) = engaged {
+        if let Some(likes) = selection.min_favs {
+            filters.push(format!("keep likes >= {}", likes));
+        }
+        if let Some(retweets) = selection.min_retweets {
+            filters.push(format!("keep retweets >= {}", retweets));
+        }
+        let unengaged = !engaged;
+        skip_unless(&mut filtered_data, &mut skipped, "engagement", |tweet| unengaged.matches(tweet, now));
+    }
+    if !selection.keep_conversations_with.is_empty() {
+        filters.push(format!("keep conversations with {}", selection.keep_conversations_with.join(", ")));
+        // chains come from the whole archive, so a newer reply still protects its older parents.
+        let protected = thread::conversations_with(entries, &selection.keep_conversations_with);
+        skip_unless(&mut filtered_data, &mut skipped, "conversation", |tweet| !thread::id(tweet).is_some_and(|id| protected.contains(&id)));
+    }
+    if let Some(expression) = &selection.decay {
+        let decay = decay::Decay::parse(expression)?;
+        filters.push(format!("decay: {}", expression));
+        skip_unless(&mut filtered_data, &mut skipped, "decay", |tweet| post_time(tweet).is_some_and(|created_at| now - created_at > decay.allowed_age(tweet)));
+    }
+    if selection.expiry || selection.expiry_file.is_some() {
+        filters.push("per-post expiry".to_string());
+        let annotated = expiry::annotate(filtered_data.clone(), selection.expiry_file.as_deref())?;
+        skip_missing(&filtered_data, &annotated, &mut skipped, "no-expiry");
+        filtered_data = annotated;
+        if !pending {
+            skip_unless(&mut filtered_data, &mut skipped, "not-expired", |tweet| expiry::expires_at(tweet).is_some_and(|expires_at| expires_at <= now));
+        }
+    }
+    if let Some(max_length) = selection.max_length {
+        filters.push(format!("text length <= {}{}", max_length, if selection.strip_entities { " (entities stripped)" } else { "" }));
+        skip_unless(&mut filtered_data, &mut skipped, "filter:max-length", |tweet| {
+            let full_text = text::of(&tweet["tweet"]);
+            text::comparable(full_text, selection.ignore_case, selection.strip_entities).chars().count() <= max_length
+        });
+    }
+    if selection.purge_dead_links {
+        filters.push("dead links".to_string());
+        let dead = links::dead_link_posts(filtered_data.clone(), selection.link_check_concurrency).await;
+        skip_missing(&filtered_data, &dead, &mut skipped, "links-alive");
+        filtered_data = dead;
+    }
+    if let Some(after) = &selection.after {
+        let floor = parse_instant(after).with_context(|| format!("failed --after parse. (format %Y-%m-%d or RFC 3339) value={}", after))?;
+        filters.push(format!("created_at >= {}", after));
+        let recent = Filter::created_since(floor);
+        skip_unless(&mut filtered_data, &mut skipped, "not-before", |tweet| recent.matches(tweet, now));
+    }
+    Ok(Selected { posts: thread::children_first(filtered_data), filters, skipped })
+}
+
+fn new_clock(options: &RunOptions) -> SharedClock {
+    if options.simulate {
+        let start = options.replay.as_deref().and_then(replay::started_at).unwrap_or_else(Utc::now);
+        Arc::new(SimulatedClock::new(start))
+    } else {
+        Arc::new(SystemClock)
+    }
+}
+
+/// the cutoff recorded in the metadata. --older-than is resolved against `now`, so the file says which instant it meant.
+fn metadata_cutoff(selection: &Selection, now: DateTime<Utc>) -> Option<String> {
+    selection.cutoff().map(str::to_string).or_else(|| selection.older_than.map(|age| (now - age).to_rfc3339()))
+}
+
+/// sortable by start time, and unique enough to tell overlapping runs apart, e.g. 20240102T030405Z-1a2b3c4d.
+fn new_run_id(now: DateTime<Utc>) -> String {
+    format!("{}-{:08x}", now.format("%Y%m%dT%H%M%SZ"), rand::random::<u32>())
+}
+
+fn new_metadata(cutoff: Option<String>, filters: Vec<String>, total: usize, candidates: usize) -> RunMetadata {
+    let run_at = Utc::now();
+    RunMetadata {
+        schema_version: schema::STATE,
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        run_at,
+        run_id: new_run_id(run_at),
+        cutoff,
+        filters,
+        total,
+        candidates,
+        processed: 0,
+        remaining: candidates,
+        failed: 0,
+        account_id: None,
+        parts: BTreeMap::new(),
+        requires_approval: false,
+        outcomes: BTreeMap::new(),
+    }
+}
+
+/// the command line, `args` with the program name first.
+pub async fn cli_main(args: Vec<std::ffi::OsString>) -> Result<()> {
+    if version::verbose_requested(&args) {
+        version::print_verbose();
+        return Ok(());
+    }
+    // before the variables are attached, since clap reads them right then, and before .env, so a
+    // profile's credentials aren't shadowed by those of the default account.
+    let command = Cli::command();
+    config::load(&command, &args)?;
+    dotenv().ok();
+    let command = config::with_env(command);
+    let cli = Cli::from_arg_matches(&command.get_matches_from(&args)).unwrap_or_else(|err| err.exit());
+    color::init(cli.color);
+    if let Some(path) = &cli.log_file {
+        log::init(path, cli.log_level, Rotation { max_size: cli.run.rotate_size, keep: cli.run.rotate_keep })?;
+    }
+
+    match &cli.command {
+        #[cfg(feature = "x-v2")]
+        Some(Command::Auth { oauth2: true, redirect_uri, token_file, .. }) => {
+            return oauth2::authorize(token_file, redirect_uri.as_deref().expect("--oauth2 requires --redirect-uri.")).await;
+        },
+        #[cfg(not(feature = "x-v2"))]
+        Some(Command::Auth { oauth2: true, .. }) => {
+            anyhow::bail!("--oauth2 goes through v2. {}", api::not_compiled("v2"));
+        },
+        Some(Command::Auth { env_file, .. }) => {
+            return auth::run(env_file).await;
+        },
+        Some(Command::Doctor { archive, backup_dir }) => {
+            let problems = doctor::run(archive.as_deref(), backup_dir.as_deref()).await;
+            if problems > 0 {
+                anyhow::bail!("doctor found problems. problems={}", problems);
+            }
+            return Ok(());
+        },
+        Some(Command::Report { from_ledger, top, redact_text, timezone }) => {
+            let mut records = report::read_ledger(from_ledger)?;
+            if *redact_text {
+                records.iter_mut().for_each(LedgerRecord::redact);
+            }
+            report::print(&records, *top, *timezone);
+            return Ok(());
+        },
+        Some(Command::Backup { command: BackupCommand::Verify { ledger, backup_dir } }) => {
+            let problems = backup::verify(&report::read_ledger(ledger)?, backup_dir);
+            if problems > 0 {
+                anyhow::bail!("backup verification failed. problems={}", problems);
+            }
+            return Ok(());
+        },
+        Some(Command::Restore { command: RestoreCommand::Plan { pattern, backup_dir, out } }) => {
+            return restore::plan(&backup::read_all(backup_dir)?, backup_dir, pattern, out);
+        },
+        Some(Command::Export { command: ExportCommand::Markdown { backup_dir, out, timezone } }) => {
+            return export::markdown(&backup::read_all(backup_dir)?, backup_dir, out, *timezone);
+        },
+        Some(Command::Export { command: ExportCommand::Hugo { backup_dir, out, timezone } }) => {
+            return export::blog(&backup::read_all(backup_dir)?, backup_dir, out, export::Blog::Hugo, *timezone);
+        },
+        Some(Command::Export { command: ExportCommand::Jekyll { backup_dir, out, timezone } }) => {
+            return export::blog(&backup::read_all(backup_dir)?, backup_dir, out, export::Blog::Jekyll, *timezone);
+        },
+        Some(Command::Export { command: ExportCommand::Sqlite { db, archive } }) => {
+            return sqlite::export(&get_entries(archive)?, db);
+        },
+        Some(Command::Plan { selection, out, split_by_quota, daily_limit, compare, skipped_csv, require_approval }) => {
+            let tweets = selection.tweets.as_deref().expect("tweets is required.");
+            let mut entries = get_entries(tweets)?;
+            // plan takes no --auth, so it fetches with the default one.
+            archive::fill_gap(tweets, &mut entries, selection.supplement_api, &RunOptions::default()).await?;
+            let now = Utc::now();
+            let selected = select(selection, &entries, false, now).await?;
+            if let Some(previous) = compare {
+                // read before `out` is written, which may well be the same file.
+                plan::compare(previous, &selected.posts)?;
+            }
+            if let Some(path) = skipped_csv {
+                plan::write_skipped_csv(path, &selected.skipped)?;
+            }
+            let mut metadata = new_metadata(metadata_cutoff(selection, now), selected.filters, entries.len(), selected.posts.len());
+            metadata.requires_approval = *require_approval;
+            let chunk_size = if *split_by_quota { *daily_limit } else { None };
+            return plan::write(out, selected.posts, metadata, chunk_size, &selected.skipped);
+        },
+        Some(Command::Nuke { tweets, cooling_off, yes_delete_everything, run: options }) => {
+            return nuke::nuke(tweets, *cooling_off, *yes_delete_everything, options).await;
+        },
+        Some(Command::Likes { likes, run: options }) => {
+            return likes::unlike(likes, options).await;
+        },
+        #[cfg(feature = "x-v2")]
+        Some(Command::Bookmarks { before, state, run: options }) => {
+            return bookmarks::remove(before, state, options).await;
+        },
+        #[cfg(not(feature = "x-v2"))]
+        Some(Command::Bookmarks { .. }) => {
+            anyhow::bail!("bookmarks are only in v2. {}", api::not_compiled("v2"));
+        },
+        Some(Command::Dm { messages, before, run: options }) => {
+            return dm::delete(messages, before, options).await;
+        },
+        Some(Command::Approve { plan, approver, hash, approvals }) => {
+            return approval::approve(plan, approver, hash.as_deref(), approvals);
+        },
+        Some(Command::Apply { plan, approvals, run: options }) => {
+            let (posts, mut metadata) = plan::read(plan)?;
+            match approvals {
+                Some(approvals) => approval::check(&posts, approvals)?,
+                None if metadata.requires_approval => anyhow::bail!("plan requires approval. pass --approvals. path={}", plan.display()),
+                None => {},
+            }
+            // applying is a run of its own, not the one that wrote the plan.
+            metadata.run_id = new_run_id(Utc::now());
+            return run(posts, plan.clone(), metadata, options, new_clock(options)).await;
+        },
+        None => {},
+    }
+
+    if cli.install_service || cli.print_service {
+        let service = systemd::service_unit(args)?;
+        let timer = systemd::timer_unit(&cli.schedule);
+        if cli.print_service {
+            println!("# post_remove.service\n{}\n# post_remove.timer\n{}", service, timer);
+        } else {
+            let dir = match &cli.service_dir {
+                Some(dir) => dir.clone(),
+                None => systemd::default_dir()?,
+            };
+            systemd::install(&dir, &service, &timer)?;
+        }
+        return Ok(());
+    }
+
+    // clap guarantees this when no subcommand is given.
+    let tweets_path = cli.selection.tweets.clone().expect("tweets is required.");
+    let (mut entries, previous, state_path) = resume_state(get_input(&tweets_path)?, &cli.run)?;
+    if previous.is_none() {
+        // a remaining file carries its own candidates. only a fresh archive can have a gap.
+        archive::fill_gap(&tweets_path, &mut entries, cli.selection.supplement_api, &cli.run).await?;
+    }
+    // likes have no date, so the selection isn't for them. a resumed run has them in its state file.
+    let (mut likes, entries): (Vec<Value>, Vec<Value>) = entries.into_iter().partition(|entry| entry["like"].is_object());
+    if let Some(path) = cli.selection.with_likes.as_deref().filter(|_| previous.is_none()) {
+        likes = likes::entries(path)?;
+    }
+    let clock = new_clock(&cli.run);
+    let now = clock.now();
+    let mut selected = select(&cli.selection, &entries, cli.run.daemon, now).await?;
+    if !likes.is_empty() {
+        selected.filters.push("likes".to_string());
+    }
+    let mut metadata = new_metadata(metadata_cutoff(&cli.selection, now), selected.filters, entries.len() + likes.len(), selected.posts.len() + likes.len());
+    metadata.resume(previous);
+    selected.posts.extend(likes);
+    // the candidates are copies. the whole archive needn't stay around for the run.
+    drop(entries);
+    run(selected.posts, state_path, metadata, &cli.run, clock).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use serde_json::json;
+
+    fn post(id: u64, created_at: &str, text: &str, likes: u64) -> Value {
+        json!({ "tweet": { "id": id.to_string(), "created_at": created_at, "full_text": text, "favorite_count": likes.to_string() } })
+    }
+
+    fn selection(args: &[&str]) -> Selection {
+        Cli::try_parse_from(["post_remove"].iter().chain(args).chain(&["tweets.json"])).unwrap().selection
+    }
+
+    #[test]
+    fn reads_tweets_js_and_plain_json() {
+        let parse = |data: &[u8]| parse_tweets_data(BufReader::new(data)).unwrap();
+        let expected = json!([{ "tweet": { "id": "1" } }]);
+        assert_eq!(parse(b"window.YTD.tweets.part0 = [{ \"tweet\": { \"id\": \"1\" } }];\n"), expected);
+        assert_eq!(parse(b"\xEF\xBB\xBF  window.YTD.tweets.part0 = [{ \"tweet\": { \"id\": \"1\" } }]"), expected);
+        assert_eq!(parse(b"[{ \"tweet\": { \"id\": \"1\" } }]"), expected);
+        assert!(parse_tweets_data(BufReader::new(&b"window.YTD.tweets.part0 = ["[..])).is_err());
+    }
+
+    #[tokio::test]
+    async fn every_left_out_post_has_its_reason() {
+        let entries = vec![
+            post(1, "Mon Jan 01 10:00:00 +0000 2024", "old", 0),
+            post(2, "Mon Jan 01 10:00:00 +0000 2024", "keep this", 0),
+            post(3, "Mon Jan 01 10:00:00 +0000 2024", "popular", 9),
+            post(4, "Sat Jun 01 10:00:00 +0000 2024", "new", 0),
+        ];
+        let selection = selection(&["--before", "2024-03-01", "--exclude", "keep", "--min-favs", "5"]);
+        let now = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().to_utc();
+        let selected = select(&selection, &entries, false, now).await.unwrap();
+        assert_eq!(selected.posts.iter().filter_map(thread::id).collect::<Vec<_>>(), [1]);
+        let reasons: Vec<(u64, &str)> = selected.skipped.iter().map(|skip| (skip.id, skip.reason)).collect();
+        assert_eq!(reasons, [(4, "min-age"), (2, "exclude"), (3, "engagement")]);
+    }
+
+    #[test]
+    fn resume_picks_up_the_state_file() {
+        let dir = std::env::temp_dir().join(format!("post_remove-resume-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("tweets.json");
+        let posts = [1, 2, 3].map(|id| post(id, "Mon Jan 01 10:00:00 +0000 2024", "post", 0));
+        std::fs::write(&input, serde_json::to_vec(&posts).unwrap()).unwrap();
+        let mut options = RunOptions::default();
+
+        let (entries, metadata, state_path) = resume_state(get_input(&input).unwrap(), &options).unwrap();
+        assert_eq!((entries.len(), metadata.is_none()), (3, true));
+        assert_eq!(state_path, dir.join("tweets.json.state.json"));
+
+        let state = json!({ "metadata": new_metadata(None, Vec::new(), 3, 3), "tweets": [posts[2]] });
+        std::fs::write(&state_path, serde_json::to_vec(&state).unwrap()).unwrap();
+        // a new run never quietly continues an old one.
+        assert!(resume_state(get_input(&input).unwrap(), &options).is_err());
+        options.resume = true;
+        let (entries, metadata, resumed_path) = resume_state(get_input(&input).unwrap(), &options).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries.iter().filter_map(thread::id).collect::<Vec<_>>(), [3]);
+        assert_eq!(metadata.map(|metadata| metadata.total), Some(3));
+        assert_eq!(resumed_path, state_path);
+    }
+}
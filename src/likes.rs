@@ -0,0 +1,59 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::{cli::RunOptions, get_file_input, get_state, new_clock, new_metadata, resume_state, engine::run};
+
+/// like.js itself, or the one in an archive directory.
+fn like_js(path: &Path) -> PathBuf {
+    if !path.is_dir() {
+        return path.to_path_buf();
+    }
+    let data = path.join("data").join("like.js");
+    if data.exists() {
+        data
+    } else {
+        path.join("like.js")
+    }
+}
+
+/// gives a like.js entry the `tweet` object a run works on. the `like` object stays, since it's
+/// what marks the entry as a like. likes have no date, so nothing here can be selected by age.
+fn as_entry(mut entry: Value) -> Option<Value> {
+    if entry["tweet"].is_object() {
+        // already converted, by the run that wrote this remaining file.
+        return Some(entry);
+    }
+    let id = entry["like"]["tweetId"].as_str()?.to_string();
+    let text = entry["like"]["fullText"].as_str().unwrap_or_default().to_string();
+    entry["tweet"] = json!({ "id": id, "id_str": id, "full_text": text });
+    Some(entry)
+}
+
+fn convert(entries: Vec<Value>) -> Vec<Value> {
+    let total = entries.len();
+    let likes: Vec<Value> = entries.into_iter().filter_map(as_entry).collect();
+    if likes.len() < total {
+        eprintln!("{} entries without a tweetId are skipped. entries={}", crate::color::warning(), total - likes.len());
+    }
+    likes
+}
+
+/// every like of like.js, or of the one in an archive directory, as entries a run works on.
+pub fn entries(path: &Path) -> Result<Vec<Value>> {
+    let path = like_js(path);
+    let likes = convert(get_state(&path)?.0);
+    println!("loaded likes. path={} likes={}", path.display(), likes.len());
+    Ok(likes)
+}
+
+/// removes every like of like.js. whatever is left goes to its state file, like tweets.json's.
+pub async fn unlike(path: &Path, options: &RunOptions) -> Result<()> {
+    anyhow::ensure!(options.backup_dir.is_none(), "--backup-dir doesn't apply to likes. the liked posts aren't yours.");
+    let (entries, previous, state_path) = resume_state(get_file_input(&like_js(path))?, options)?;
+    let total = entries.len();
+    let likes = convert(entries);
+    let mut metadata = new_metadata(None, vec!["likes".to_string()], total, likes.len());
+    metadata.resume(previous);
+    run(likes, state_path, metadata, options, new_clock(options)).await
+}
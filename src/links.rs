@@ -0,0 +1,61 @@
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::Duration};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+fn urls(tweet: &Value) -> Vec<String> {
+    tweet["tweet"]["entities"]["urls"].as_array()
+        .map(|urls| urls.iter().filter_map(|url| url["expanded_url"].as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// true only when the server says the page is gone. network errors count as alive.
+async fn is_dead(client: &Client, url: &str) -> bool {
+    let mut response = client.head(url).send().await;
+    // some servers don't implement HEAD.
+    if matches!(&response, Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED) {
+        response = client.get(url).send().await;
+    }
+    match response {
+        Ok(response) => matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE),
+        Err(err) => {
+            eprintln!("failed to check link. url={} err={}", url, err);
+            false
+        },
+    }
+}
+
+/// link-share posts whose every link now returns 404/410. each url is checked once.
+pub async fn dead_link_posts(posts: Vec<Value>, concurrency: usize) -> Vec<Value> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("failed to build http client.");
+    let unique: HashSet<String> = posts.iter().flat_map(urls).collect();
+    println!("checking links. urls={}", unique.len());
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut checks = JoinSet::new();
+    for url in unique {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        checks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed.");
+            let dead = is_dead(&client, &url).await;
+            (url, dead)
+        });
+    }
+    let mut dead: HashMap<String, bool> = HashMap::new();
+    while let Some(result) = checks.join_next().await {
+        let (url, is_dead) = result.expect("link check panicked.");
+        if is_dead {
+            println!("dead link. url={}", url);
+        }
+        dead.insert(url, is_dead);
+    }
+
+    posts.into_iter().filter(|tweet| {
+        let urls = urls(tweet);
+        !urls.is_empty() && urls.iter().all(|url| dead.get(url).copied().unwrap_or(false))
+    }).collect()
+}
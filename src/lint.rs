@@ -0,0 +1,40 @@
+use chrono::{DateTime, Datelike, Duration, Utc};
+
+use crate::{cli::Selection, decay::Decay, parse_instant, parse_month_day};
+
+/// combinations of selection flags that can never match or have no effect. these are only warnings,
+/// since the run itself is still well defined, but they are usually how a policy selects more or less than meant.
+pub fn check(selection: &Selection, now: DateTime<Utc>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if selection.time.is_some() {
+        warnings.push("the positional cutoff is deprecated. pass it as --before instead.".to_string());
+    }
+    let cutoff = selection.cutoff().and_then(parse_instant).or(selection.older_than.map(|age| now - age));
+    let floor = selection.after.as_deref().and_then(parse_instant);
+    if let (Some(cutoff), Some(floor)) = (cutoff, floor) {
+        if cutoff <= floor {
+            warnings.push(format!("no post can be created before {} and at or after {}. nothing will be selected.", cutoff, floor));
+        }
+    }
+    if let (Some(anniversary), Some(cutoff), Some(floor)) = (selection.keep_anniversaries.as_deref().and_then(|value| parse_month_day(value, now)), cutoff, floor) {
+        // a window of a single day that is the kept day deletes nothing.
+        if cutoff - floor <= Duration::days(1) && (floor.month(), floor.day()) == anniversary {
+            warnings.push("--keep-anniversaries keeps the whole --after window. nothing will be selected.".to_string());
+        }
+    }
+    if let Some(expression) = &selection.decay {
+        if let Ok(decay) = Decay::parse(expression) {
+            warnings.extend(decay.lint());
+        }
+    }
+    if selection.only.iter().any(|kind| selection.keep.contains(kind)) {
+        warnings.push("a kind of post is both in --only and --keep. posts of that kind are kept.".to_string());
+    }
+    if selection.ids_file.is_some() && (selection.cutoff().is_some() || selection.older_than.is_some()) {
+        warnings.push("the cutoff is ignored with --ids-file.".to_string());
+    }
+    if selection.delete_thread.is_some() && selection.dedupe {
+        warnings.push("--dedupe is ignored with --delete-thread.".to_string());
+    }
+    warnings
+}
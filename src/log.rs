@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt, Layer};
+
+use crate::ledger::Rotation;
+
+/// sends the tracing events of this crate to `path`, one JSON object per line, with their fields
+/// (id, action, status, reason, ...) as keys. stdout and stderr stay as they are. the file is
+/// rotated like the ledger when a run starts, so a multi-day daemon keeps one file per restart.
+pub fn init(path: &Path, level: LevelFilter, rotation: Rotation) -> Result<()> {
+    rotation.rotate_if_needed(path)?;
+    let file = OpenOptions::new().create(true).append(true).open(path)
+        .with_context(|| format!("failed to open log file. path={}", path.display()))?;
+    let layer = tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_writer(Mutex::new(file));
+    // reqwest and hyper log every connection at debug.
+    let targets = Targets::new().with_target(env!("CARGO_CRATE_NAME"), level);
+    tracing_subscriber::registry().with(layer.with_filter(targets)).try_init()
+        .context("failed to set up logging.")?;
+    Ok(())
+}
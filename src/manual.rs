@@ -0,0 +1,127 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use crate::{report, text};
+
+/// why a post needs a person to finish it.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// posted in a Community, where the API doesn't always let the author delete.
+    Community,
+    /// a 403. the app can't delete it, however often it retries.
+    Permission,
+    /// its backup failed, so it was kept.
+    Backup,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] = [Kind::Community, Kind::Permission, Kind::Backup];
+
+    fn title(self) -> &'static str {
+        match self {
+            Kind::Community => "Community posts",
+            Kind::Permission => "Permission errors",
+            Kind::Backup => "Failed backups",
+        }
+    }
+
+    fn next_step(self) -> &'static str {
+        match self {
+            Kind::Community => "delete it on x.com or in the app. the API doesn't always allow deleting posts in a Community.",
+            Kind::Permission => "check that the app has read and write access and the post is yours, then delete it on x.com.",
+            Kind::Backup => "fix what the error says and run again with --resume, or save the post by hand and delete it on x.com.",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ManualAction {
+    pub id: u64,
+    pub url: String,
+    pub kind: Kind,
+    /// the start of the text, or its hash with --redact-text.
+    pub text: String,
+    /// the status or error that made it one.
+    pub detail: String,
+    pub next_step: &'static str,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    run_id: &'a str,
+    actions: &'a [ManualAction],
+}
+
+/// what a run couldn't do by itself, for --manual-actions. written when the run ends, as JSON when
+/// the path ends in .json and as a Markdown checklist otherwise.
+pub struct ManualActions {
+    path: PathBuf,
+    run_id: String,
+    redact_text: bool,
+    actions: Vec<ManualAction>,
+}
+
+impl ManualActions {
+    pub fn new(path: PathBuf, run_id: &str, redact_text: bool) -> Self {
+        Self { path, run_id: run_id.to_string(), redact_text, actions: Vec::new() }
+    }
+
+    /// a post that couldn't be deleted. only failures another run wouldn't fix are listed.
+    /// `tweet` is the `tweet` object of the entry.
+    pub fn failed(&mut self, id: u64, tweet: &Value, status: Option<u16>) {
+        let kind = if is_community(tweet) {
+            Kind::Community
+        } else if status == Some(403) {
+            Kind::Permission
+        } else {
+            return;
+        };
+        let detail = status.map_or_else(|| "no response".to_string(), |status| format!("status={}", status));
+        self.push(id, tweet, kind, detail);
+    }
+
+    pub fn backup_failed(&mut self, id: u64, tweet: &Value, err: &anyhow::Error) {
+        self.push(id, tweet, Kind::Backup, format!("{:#}", err));
+    }
+
+    fn push(&mut self, id: u64, tweet: &Value, kind: Kind, detail: String) {
+        let text = if self.redact_text { text::redact(text::of(tweet)) } else { report::preview(text::of(tweet)) };
+        let url = format!("https://x.com/i/web/status/{}", id);
+        self.actions.push(ManualAction { id, url, kind, text, detail, next_step: kind.next_step() });
+    }
+
+    pub fn write(&self) -> Result<()> {
+        if self.path.extension().is_some_and(|extension| extension == "json") {
+            let document = Document { schema_version: crate::schema::MANUAL, run_id: &self.run_id, actions: &self.actions };
+            crate::write_atomically(&self.path, &serde_json::to_value(document)?)?;
+        } else {
+            fs::write(&self.path, self.markdown())?;
+        }
+        println!("wrote manual actions. path={} actions={}", self.path.display(), self.actions.len());
+        Ok(())
+    }
+
+    fn markdown(&self) -> String {
+        let mut markdown = format!("# Manual actions\n\nposts to finish by hand: {}. run_id={}\n", self.actions.len(), self.run_id);
+        for kind in Kind::ALL {
+            let actions: Vec<&ManualAction> = self.actions.iter().filter(|action| action.kind == kind).collect();
+            if actions.is_empty() {
+                continue;
+            }
+            let _ = write!(markdown, "\n## {}\n\n{}\n\n", kind.title(), kind.next_step());
+            for action in actions {
+                let _ = writeln!(markdown, "- [ ] [{}]({}) {} ({})", action.id, action.url, action.text, action.detail);
+            }
+        }
+        markdown
+    }
+}
+
+/// archives mark posts made in a Community with its id.
+fn is_community(tweet: &Value) -> bool {
+    tweet["community_id_str"].is_string() || tweet["community_id"].is_string()
+}
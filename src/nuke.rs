@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs::{self, File}, path::{Path, PathBuf}};
+
+use crate::{cli::RunOptions, confirm, confirm_phrase, get_input, new_clock, new_metadata, resume_state, engine::run, thread, Error};
+
+/// typed in full before the account is armed, so a stray `y` can't do it.
+const PHRASE: &str = "delete everything";
+
+/// `<backup-dir>/nuke.json`. its presence arms the account, its age ends the cooling-off.
+#[derive(Serialize, Deserialize)]
+struct Armed {
+    armed_at: DateTime<Utc>,
+    tweets: PathBuf,
+    posts: usize,
+}
+
+fn armed_path(backup_dir: &Path) -> PathBuf {
+    backup_dir.join("nuke.json")
+}
+
+/// deletes every post of the archive, in two steps. the first invocation confirms twice and arms;
+/// only an invocation after `cooling_off` deletes, with every post backed up first, and disarms when
+/// that run ends. delete nuke.json to disarm by hand. --simulate never writes or removes it. `accepted` stands in for the typed phrase without a terminal.
+pub async fn nuke(tweets: &Path, cooling_off: chrono::Duration, accepted: bool, options: &RunOptions) -> Result<()> {
+    let backup_dir = options.backup_dir.clone().context("nuke needs --backup-dir. every post is backed up before it goes.")?;
+    let (entries, previous, state_path) = resume_state(get_input(tweets)?, options)?;
+    let path = armed_path(&backup_dir);
+    let clock = new_clock(options);
+
+    let armed: Option<Armed> = match File::open(&path) {
+        Ok(file) => Some(serde_json::from_reader(file).with_context(|| format!("invalid {}. delete it to disarm.", path.display()))?),
+        Err(_) => None,
+    };
+    if options.dry_run {
+        let metadata = new_metadata(None, vec!["everything (nuke)".to_string()], entries.len(), entries.len());
+        return run(thread::children_first(entries), state_path, metadata, options, clock).await;
+    }
+    let Some(armed) = armed else {
+        println!("nuke selects every post of the archive regardless of date. posts={}", entries.len());
+        if !confirm(options, &format!("arm deletion of all {} posts?", entries.len()))? || !confirm_phrase(options, PHRASE, accepted)? {
+            return Err(Error::Cancelled.into());
+        }
+        // the wall clock, not the run's: a --replay clock starts in the past and would shorten the cooling-off.
+        let armed = Armed { armed_at: Utc::now(), tweets: tweets.to_path_buf(), posts: entries.len() };
+        if options.simulate {
+            println!("simulate. nuke not armed. {} is left as it is.", path.display());
+            return Ok(());
+        }
+        fs::create_dir_all(&backup_dir).with_context(|| format!("failed to create {}.", backup_dir.display()))?;
+        fs::write(&path, serde_json::to_vec_pretty(&armed)?).with_context(|| format!("failed to write {}.", path.display()))?;
+        println!("nuke armed. run the same command again after {}. delete {} to disarm.", (armed.armed_at + cooling_off).format("%Y-%m-%d %H:%M:%S UTC"), path.display());
+        return Ok(());
+    };
+
+    let ready_at = armed.armed_at + cooling_off;
+    if Utc::now() < ready_at {
+        anyhow::bail!("cooling off. nuke can run after {}. delete {} to disarm.", ready_at.format("%Y-%m-%d %H:%M:%S UTC"), path.display());
+    }
+    println!("cooling-off is over. armed at {}. posts={}", armed.armed_at, entries.len());
+    if !confirm_phrase(options, PHRASE, accepted)? {
+        return Err(Error::Cancelled.into());
+    }
+    let mut metadata = new_metadata(None, vec!["everything (nuke)".to_string()], entries.len(), entries.len());
+    metadata.resume(previous);
+    // run asks once more with the final count.
+    let result = run(thread::children_first(entries), state_path, metadata, options, clock).await;
+    if options.simulate {
+        // nothing was deleted, so the real run is still to come.
+        println!("simulate. nuke stays armed. path={}", path.display());
+        return result;
+    }
+    match fs::remove_file(&path) {
+        Ok(()) => println!("nuke disarmed. removed {}.", path.display()),
+        Err(err) => eprintln!("{} failed to remove {}. delete it to disarm. err={}", crate::color::warning(), path.display(), err),
+    }
+    result
+}
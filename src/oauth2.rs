@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Response, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{env, fs, io::{self, IsTerminal, Write}, path::{Path, PathBuf}};
+
+use crate::{schema, Account};
+
+const AUTHORIZE: &str = "https://x.com/i/oauth2/authorize";
+const TOKEN: &str = "https://api.x.com/2/oauth2/token";
+const ME: &str = "https://api.x.com/2/users/me";
+/// offline.access is what gets a refresh token. like.write is for the likes subcommand, dm.* for dm
+/// and bookmark.* for bookmarks.
+const SCOPES: &str = "tweet.read tweet.write users.read like.write dm.read dm.write bookmark.read bookmark.write offline.access";
+/// a token this close to expiry is refreshed before it's used.
+const REFRESH_MARGIN: chrono::Duration = chrono::Duration::minutes(1);
+
+/// the token file written by `auth --oauth2` and rewritten on every refresh.
+#[derive(Serialize, Deserialize)]
+struct Token {
+    #[serde(default)]
+    schema_version: u32,
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+/// OAUTH2_CLIENT_ID, and OAUTH2_CLIENT_SECRET for confidential clients.
+struct Client {
+    id: String,
+    secret: Option<String>,
+}
+
+impl Client {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            id: env::var("OAUTH2_CLIENT_ID").context("OAUTH2_CLIENT_ID not found in environment.")?,
+            secret: env::var("OAUTH2_CLIENT_SECRET").ok(),
+        })
+    }
+
+    async fn token(&self, form: &[(&str, &str)]) -> Result<Token> {
+        let mut request = reqwest::Client::new().post(TOKEN).form(&[form, &[("client_id", self.id.as_str())]].concat());
+        if let Some(secret) = &self.secret {
+            request = request.basic_auth(&self.id, Some(secret));
+        }
+        let response = request.send().await.with_context(|| format!("failed to reach the API. url={}", TOKEN))?;
+        let status = response.status();
+        let body: Value = response.json().await.context("invalid token response.")?;
+        anyhow::ensure!(status.is_success(), "token request rejected. status={} error={}", status, body["error_description"].as_str().or(body["error"].as_str()).unwrap_or_default());
+        Ok(Token {
+            schema_version: schema::OAUTH2_TOKEN,
+            access_token: body["access_token"].as_str().context("token response has no access_token.")?.to_string(),
+            refresh_token: body["refresh_token"].as_str().map(str::to_string),
+            expires_at: Utc::now() + chrono::Duration::seconds(body["expires_in"].as_i64().unwrap_or(7200)),
+        })
+    }
+}
+
+fn random_string(bytes: usize) -> String {
+    URL_SAFE_NO_PAD.encode((0..bytes).map(|_| rand::random::<u8>()).collect::<Vec<u8>>())
+}
+
+fn save(path: &Path, token: &Token) -> Result<()> {
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, serde_json::to_vec_pretty(token)?).with_context(|| format!("failed to write {}.", temporary.display()))?;
+    // the file holds secrets.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temporary, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&temporary, path).with_context(|| format!("failed to write {}.", path.display()))?;
+    Ok(())
+}
+
+/// authorization code flow with PKCE. the user opens the authorize url, approves the app and
+/// pastes the url the browser was redirected to, so no local server is needed.
+pub async fn authorize(token_file: &Path, redirect_uri: &str) -> Result<()> {
+    let client = Client::from_env()?;
+    anyhow::ensure!(io::stdin().is_terminal(), "auth needs a terminal to read the redirect url from.");
+    let verifier = random_string(32);
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = random_string(16);
+    let url = Url::parse_with_params(AUTHORIZE, &[
+        ("response_type", "code"),
+        ("client_id", client.id.as_str()),
+        ("redirect_uri", redirect_uri),
+        ("scope", SCOPES),
+        ("state", state.as_str()),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ])?;
+    println!("open this url, authorize the app and paste the url you are redirected to.");
+    println!("{}", url);
+    print!("redirect url: ");
+    io::stdout().flush()?;
+    let mut redirected = String::new();
+    io::stdin().read_line(&mut redirected)?;
+    let redirected = Url::parse(redirected.trim()).context("invalid redirect url.")?;
+    let query = |key: &str| redirected.query_pairs().find(|(name, _)| name == key).map(|(_, value)| value.into_owned());
+    anyhow::ensure!(query("state").as_deref() == Some(state.as_str()), "state of the redirect url doesn't match. start over.");
+    let code = query("code").context("the redirect url has no code.")?;
+
+    let token = client.token(&[
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier.as_str()),
+    ]).await?;
+    anyhow::ensure!(token.refresh_token.is_some(), "no refresh token returned. the app needs the offline.access scope.");
+    save(token_file, &token)?;
+    let account = me(&token.access_token).await?;
+    println!("authorized. id={} screen_name={} token_file={}", account.id, account.screen_name, token_file.display());
+    Ok(())
+}
+
+async fn me(access_token: &str) -> Result<Account> {
+    let user: Value = reqwest::Client::new()
+        .get(ME)
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()
+        .context("failed to verify credentials.")?
+        .json()
+        .await?;
+    let id = user["data"]["id"].as_str().context("users/me returned no id.")?;
+    Ok(Account { id: id.to_string(), screen_name: user["data"]["username"].as_str().unwrap_or_default().to_string(), access_level: None })
+}
+
+/// a user-context token that refreshes itself, so a run can outlive the two hours a token lasts.
+pub struct Session {
+    client: Client,
+    path: PathBuf,
+    token: tokio::sync::Mutex<Token>,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("token file not found. run auth --oauth2 first. path={}", path.display()))?;
+        let token: Token = serde_json::from_slice(&bytes).with_context(|| format!("invalid token file. path={}", path.display()))?;
+        anyhow::ensure!(token.schema_version <= schema::OAUTH2_TOKEN, "token file was written by a newer post_remove. path={}", path.display());
+        Ok(Self { client: Client::from_env()?, path: path.to_path_buf(), token: tokio::sync::Mutex::new(token) })
+    }
+
+    /// refreshes and stores the new tokens. refresh tokens are single-use, so the file has to be rewritten.
+    async fn refresh(&self, token: &mut Token) -> Result<()> {
+        let refresh_token = token.refresh_token.clone().context("no refresh token. run auth --oauth2 again.")?;
+        let mut refreshed = self.client.token(&[("grant_type", "refresh_token"), ("refresh_token", refresh_token.as_str())]).await?;
+        // some providers keep the refresh token and leave it out of the response.
+        refreshed.refresh_token = refreshed.refresh_token.or(Some(refresh_token));
+        save(&self.path, &refreshed)?;
+        *token = refreshed;
+        println!("refreshed access token. expires_at={}", token.expires_at);
+        Ok(())
+    }
+
+    async fn access_token(&self, force_refresh: bool) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if force_refresh || token.expires_at - REFRESH_MARGIN <= Utc::now() {
+            self.refresh(&mut token).await?;
+        }
+        Ok(token.access_token.clone())
+    }
+
+    pub async fn account(&self) -> Result<Account> {
+        me(&self.access_token(false).await?).await
+    }
+
+    /// a 401 refreshes the token and tries once more, in case it was revoked early.
+    pub async fn send(&self, method: Method, url: &str) -> Result<Response> {
+        let client = reqwest::Client::new();
+        let response = client.request(method.clone(), url).bearer_auth(self.access_token(false).await?).send().await?;
+        if response.status().as_u16() != 401 {
+            return Ok(response);
+        }
+        Ok(client.request(method, url).bearer_auth(self.access_token(true).await?).send().await?)
+    }
+}
@@ -0,0 +1,91 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// outcomes the error rate is computed over.
+const WINDOW: usize = 20;
+const MAX_DELAY: Duration = Duration::from_secs(120);
+
+/// how hard the run pushes while requests keep failing, so a failure loop doesn't keep hammering
+/// the account: fewer posts in flight first, then a longer pause between posts. both come back
+/// once requests succeed again.
+pub struct Pace {
+    base: Duration,
+    delay: Duration,
+    max_concurrency: usize,
+    concurrency: usize,
+    threshold: f64,
+    recent: VecDeque<bool>,
+}
+
+impl Pace {
+    pub fn new(base: Duration, concurrency: usize, threshold: f64) -> Self {
+        let concurrency = concurrency.max(1);
+        Self { base, delay: base, max_concurrency: concurrency, concurrency, threshold, recent: VecDeque::with_capacity(WINDOW) }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// how many posts may be in flight, at most --concurrency.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    fn error_rate(&self) -> f64 {
+        self.recent.iter().filter(|failed| **failed).count() as f64 / self.recent.len().max(1) as f64
+    }
+
+    /// above the threshold, halves the concurrency down to 1 and then doubles the delay. once the
+    /// rate drops below half of it, halves the delay back to the base and then doubles the concurrency.
+    pub fn record(&mut self, failed: bool) {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(failed);
+        let error_rate = self.error_rate();
+        let previous = (self.concurrency, self.delay);
+        if failed && error_rate > self.threshold {
+            if self.concurrency > 1 {
+                self.concurrency /= 2;
+            } else {
+                self.delay = (self.delay * 2).min(MAX_DELAY);
+            }
+        } else if !failed && error_rate < self.threshold / 2.0 {
+            if self.delay > self.base {
+                self.delay = (self.delay / 2).max(self.base);
+            } else {
+                self.concurrency = (self.concurrency * 2).min(self.max_concurrency);
+            }
+        }
+        if self.concurrency < previous.0 || self.delay > previous.1 {
+            eprintln!("{} slowing down. error_rate={:.2} concurrency={} delay={}s", crate::color::warning(), error_rate, self.concurrency, self.delay.as_secs());
+        } else if self.concurrency > previous.0 || self.delay < previous.1 {
+            println!("speeding up. error_rate={:.2} concurrency={} delay={}s", error_rate, self.concurrency, self.delay.as_secs());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_concurrency_before_pausing_longer() {
+        let base = Duration::from_secs(3);
+        let mut pace = Pace::new(base, 4, 0.2);
+        pace.record(true);
+        assert_eq!((pace.concurrency(), pace.delay()), (2, base));
+        pace.record(true);
+        assert_eq!((pace.concurrency(), pace.delay()), (1, base));
+        pace.record(true);
+        assert_eq!((pace.concurrency(), pace.delay()), (1, base * 2));
+        // successes bring the error rate under half the threshold, and the pace back in reverse.
+        let mut steps = Vec::new();
+        for _ in 0..WINDOW * 2 {
+            pace.record(false);
+            steps.push((pace.concurrency(), pace.delay()));
+        }
+        steps.dedup();
+        assert_eq!(steps, [(1, base * 2), (1, base), (2, base), (4, base)]);
+    }
+}
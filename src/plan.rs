@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::{collections::HashSet, fs::File, io::{BufWriter, Write}, path::{Path, PathBuf}};
+
+use crate::{approval, journal, report::preview, schema, text, thread, RunMetadata, Skip};
+
+/// `plan.json` -> `plan-001.json`, numbered from 1 so the files sort in the order to apply them.
+fn chunk_path(out: &Path, index: usize) -> PathBuf {
+    let stem = out.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "plan".to_string());
+    let name = match out.extension() {
+        Some(extension) => format!("{}-{:03}.{}", stem, index + 1, extension.to_string_lossy()),
+        None => format!("{}-{:03}", stem, index + 1),
+    };
+    out.with_file_name(name)
+}
+
+fn write_file(path: &Path, posts: &[Value], metadata: &RunMetadata, skipped: &[Skip]) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("failed to create {}.", path.display()))?;
+    serde_json::to_writer(file, &json!({ "metadata": metadata, "tweets": posts, "skipped": skipped }))
+        .with_context(|| format!("failed to write {}.", path.display()))?;
+    println!("plan written. path={} posts={} plan_hash={}", path.display(), posts.len(), approval::plan_hash(posts));
+    Ok(())
+}
+
+/// writes the candidates in the same layout as the remaining file, so `apply` can resume a plan.
+/// with `chunk_size` the plan is split into one file per day of quota, and `skipped` goes into the first one.
+pub fn write(out: &Path, posts: Vec<Value>, mut metadata: RunMetadata, chunk_size: Option<usize>, skipped: &[Skip]) -> Result<()> {
+    let Some(chunk_size) = chunk_size else {
+        return write_file(out, &posts, &metadata, skipped);
+    };
+    anyhow::ensure!(chunk_size > 0, "--daily-limit must be at least 1.");
+    if posts.is_empty() {
+        println!("nothing to plan.");
+        return Ok(());
+    }
+    for (index, chunk) in posts.chunks(chunk_size).enumerate() {
+        metadata.candidates = chunk.len();
+        metadata.remaining = chunk.len();
+        write_file(&chunk_path(out, index), chunk, &metadata, if index == 0 { skipped } else { &[] })?;
+    }
+    Ok(())
+}
+
+/// loads a plan written by `write`. the metadata is carried over for the run that applies it.
+pub fn read(path: &Path) -> Result<(Vec<Value>, RunMetadata)> {
+    let file = File::open(path).with_context(|| format!("failed to open plan. path={}", path.display()))?;
+    let mut plan: Value = serde_json::from_reader(file).with_context(|| format!("invalid plan. path={}", path.display()))?;
+    let mut posts: Vec<Value> = serde_json::from_value(plan["tweets"].take())
+        .with_context(|| format!("plan has no tweets. path={}", path.display()))?;
+    journal::replay(path, &mut posts);
+    let mut metadata = plan["metadata"].take();
+    anyhow::ensure!(metadata.is_object(), "plan has no metadata. path={}", path.display());
+    schema::migrate_metadata(&mut metadata).with_context(|| format!("unsupported plan. path={}", path.display()))?;
+    let mut metadata: RunMetadata = serde_json::from_value(metadata)
+        .with_context(|| format!("invalid plan metadata. path={}", path.display()))?;
+    metadata.tool_version = env!("CARGO_PKG_VERSION").to_string();
+    metadata.run_at = Utc::now();
+    metadata.candidates = posts.len();
+    metadata.processed = 0;
+    metadata.failed = 0;
+    metadata.remaining = posts.len();
+    Ok((posts, metadata))
+}
+
+/// `id,reason` for every post left out of the selection.
+pub fn write_skipped_csv(path: &Path, skipped: &[Skip]) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("failed to create {}.", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "id,reason")?;
+    for skip in skipped {
+        writeln!(writer, "{},{}", skip.id, skip.reason)?;
+    }
+    writer.flush().with_context(|| format!("failed to write {}.", path.display()))?;
+    Ok(())
+}
+
+/// prints the posts that entered or left the selection since the plan at `previous` was written.
+pub fn compare(previous: &Path, posts: &[Value]) -> Result<()> {
+    let (old, _) = read(previous)?;
+    let old_ids: HashSet<u64> = old.iter().filter_map(thread::id).collect();
+    let new_ids: HashSet<u64> = posts.iter().filter_map(thread::id).collect();
+    let text = |tweet: &Value| preview(&text::display(&tweet["tweet"]));
+    for tweet in posts.iter().filter(|tweet| thread::id(tweet).is_some_and(|id| !old_ids.contains(&id))) {
+        println!("+ {} {}", tweet["tweet"]["id"].as_str().unwrap_or_default(), text(tweet));
+    }
+    for tweet in old.iter().filter(|tweet| thread::id(tweet).is_some_and(|id| !new_ids.contains(&id))) {
+        println!("- {} {}", tweet["tweet"]["id"].as_str().unwrap_or_default(), text(tweet));
+    }
+    println!("compared. previous={} added={} removed={} unchanged={}",
+        previous.display(), new_ids.difference(&old_ids).count(), old_ids.difference(&new_ids).count(), new_ids.intersection(&old_ids).count());
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use crate::clock::SharedClock;
+
+#[derive(Serialize)]
+struct Snapshot {
+    schema_version: u32,
+    processed: usize,
+    remaining: usize,
+    failed: usize,
+    eta: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+    account_id: String,
+    run_id: String,
+    /// set while the run sleeps on a rate limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    waiting: Option<Waiting>,
+}
+
+#[derive(Serialize)]
+struct Waiting {
+    reason: String,
+    resumes_at: DateTime<Utc>,
+}
+
+/// small JSON file for dashboards to poll. rewritten after every post, independent of stdout.
+pub struct ProgressFile {
+    path: PathBuf,
+    clock: SharedClock,
+    started: DateTime<Utc>,
+    account_id: String,
+    run_id: String,
+    // processed, remaining, failed of the last update, repeated while waiting.
+    counts: Mutex<(usize, usize, usize)>,
+}
+
+impl ProgressFile {
+    /// refuses a file left behind by a run for another account.
+    /// times and the ETA go by `clock`, the run's.
+    pub fn new(path: PathBuf, account_id: String, run_id: String, clock: SharedClock) -> Result<Self> {
+        if let Some(bound) = fs::read(&path).ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+            .and_then(|previous| previous["account_id"].as_str().map(str::to_string))
+        {
+            anyhow::ensure!(bound == account_id, "progress file belongs to another account. path={} expected={} actual={}",
+                path.display(), bound, account_id);
+        }
+        Ok(Self { path, started: clock.now(), clock, account_id, run_id, counts: Mutex::new((0, 0, 0)) })
+    }
+
+    pub fn update(&self, processed: usize, remaining: usize, failed: usize) -> Result<()> {
+        *self.counts.lock().expect("progress lock poisoned.") = (processed, remaining, failed);
+        self.write(None)
+    }
+
+    /// marks the run as sleeping on a rate limit until `resumes_at`. the next update clears it.
+    pub fn waiting(&self, reason: &str, resumes_at: DateTime<Utc>) -> Result<()> {
+        self.write(Some(Waiting { reason: reason.to_string(), resumes_at }))
+    }
+
+    fn write(&self, waiting: Option<Waiting>) -> Result<()> {
+        let (processed, remaining, failed) = *self.counts.lock().expect("progress lock poisoned.");
+        let now = self.clock.now();
+        // average pace of this run so far, including rate-limit waits.
+        let eta = (processed > 0).then(|| {
+            let left = (now - self.started).to_std().unwrap_or_default().mul_f64(remaining as f64 / processed as f64);
+            now + chrono::Duration::from_std(left).unwrap_or(chrono::Duration::zero())
+        });
+        let snapshot = Snapshot { schema_version: crate::schema::PROGRESS, processed, remaining, failed, eta, updated_at: now, account_id: self.account_id.clone(), run_id: self.run_id.clone(), waiting };
+        // write then rename, so a poller never reads a half-written file.
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec_pretty(&snapshot)?)
+            .with_context(|| format!("failed to write {}.", temporary.display()))?;
+        fs::rename(&temporary, &self.path).with_context(|| format!("failed to write {}.", self.path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+
+/// the per-user budget over 24 hours that newer API responses report in x-user-limit-24hour-*,
+/// beside the 15-minute window of x-rate-limit-*. the server's numbers replace ours whenever a
+/// response carries them; in between, every request sent counts against the last known remaining.
+#[derive(Default)]
+pub struct DailyQuota {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset: Option<DateTime<Utc>>,
+}
+
+fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+impl DailyQuota {
+    pub fn update(&mut self, headers: &HeaderMap) {
+        let limit = header(headers, "x-user-limit-24hour-limit");
+        let remaining = header(headers, "x-user-limit-24hour-remaining");
+        let reset = header::<i64>(headers, "x-user-limit-24hour-reset").and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+        if limit.is_none() && remaining.is_none() && reset.is_none() {
+            self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+            return;
+        }
+        self.limit = limit.or(self.limit);
+        self.remaining = remaining.or(self.remaining);
+        self.reset = reset.or(self.reset);
+    }
+
+    /// when the budget is used up, the instant it comes back.
+    pub fn exhausted_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.remaining == Some(0)).then_some(self.reset?).filter(|reset| *reset > now)
+    }
+}
+
+/// the 15-minute window of one endpoint, as x-rate-limit-* of its last response reported it.
+/// endpoints have windows of their own, so while one is used up another may still have room.
+#[derive(Default, Clone, Copy)]
+pub struct Window {
+    pub remaining: Option<u64>,
+    pub reset: Option<DateTime<Utc>>,
+}
+
+impl Window {
+    pub fn update(&mut self, headers: &HeaderMap) {
+        let remaining = header(headers, "x-rate-limit-remaining");
+        let reset = header::<i64>(headers, "x-rate-limit-reset").and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+        if remaining.is_none() && reset.is_none() {
+            self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+            return;
+        }
+        self.remaining = remaining.or(self.remaining);
+        self.reset = reset.or(self.reset);
+    }
+
+    /// requests left before the reset. unknown counts as plenty, and a passed reset as a full window.
+    pub fn left(&self, now: DateTime<Utc>) -> u64 {
+        match (self.remaining, self.reset) {
+            (Some(remaining), Some(reset)) if reset > now => remaining,
+            (Some(remaining), None) => remaining,
+            _ => u64::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs.iter().map(|(name, value)| (reqwest::header::HeaderName::from_static(name), HeaderValue::from_str(value).unwrap())).collect()
+    }
+
+    #[test]
+    fn daily_quota_counts_down_between_reports() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut quota = DailyQuota::default();
+        quota.update(&headers(&[("x-user-limit-24hour-limit", "50"), ("x-user-limit-24hour-remaining", "1"), ("x-user-limit-24hour-reset", "1700003600")]));
+        assert_eq!(quota.exhausted_until(now), None);
+        // a response without the headers spends one of the last known remaining.
+        quota.update(&HeaderMap::new());
+        assert_eq!(quota.remaining, Some(0));
+        assert_eq!(quota.limit, Some(50));
+        assert_eq!(quota.exhausted_until(now), DateTime::from_timestamp(1_700_003_600, 0));
+        assert_eq!(quota.exhausted_until(now + chrono::Duration::hours(2)), None);
+        quota.update(&HeaderMap::new());
+        assert_eq!(quota.remaining, Some(0));
+    }
+
+    #[test]
+    fn windows_are_plenty_until_reported() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut window = Window::default();
+        assert_eq!(window.left(now), u64::MAX);
+        window.update(&headers(&[("x-rate-limit-remaining", "3"), ("x-rate-limit-reset", "1700000900")]));
+        window.update(&HeaderMap::new());
+        assert_eq!(window.left(now), 2);
+        // once the reset passed, the window is full again.
+        assert_eq!(window.left(now + chrono::Duration::minutes(16)), u64::MAX);
+    }
+}
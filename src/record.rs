@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Response};
+use serde_json::{json, Value};
+use std::{path::PathBuf, sync::Mutex, time::Duration};
+
+/// response headers kept in a recording. everything else, cookies included, is left out.
+const KEPT_HEADERS: [&str; 6] = ["content-type", "date", "retry-after", "x-access-level", "x-rate-limit-", "x-user-limit-"];
+
+/// what the account id in recorded urls is replaced with.
+pub const USER_ID: &str = "USER_ID";
+
+/// the delete requests of a run and their responses, written as HAR 1.2 for bug reports.
+/// the Authorization header is never recorded, the account id in urls is replaced with USER_ID
+/// and bodies of successful responses, which are the deleted post, are left out.
+/// written when the run ends, a panic included.
+pub struct Recorder {
+    path: PathBuf,
+    user_id: String,
+    entries: Mutex<Vec<Value>>,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf, user_id: &str) -> Self {
+        Self { path, user_id: user_id.to_string(), entries: Mutex::new(Vec::new()) }
+    }
+
+    /// records the exchange and hands back a response the engine reads like the original.
+    pub async fn record(&self, method: Method, url: &str, started: DateTime<Utc>, time: Duration, response: Response) -> anyhow::Result<Response> {
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let recorded_headers: Vec<Value> = headers.iter()
+            .filter(|(name, _)| KEPT_HEADERS.iter().any(|kept| if kept.ends_with('-') { name.as_str().starts_with(kept) } else { name.as_str() == *kept }))
+            .map(|(name, value)| json!({ "name": name.as_str(), "value": value.to_str().unwrap_or_default() }))
+            .collect();
+        let mut content = json!({ "size": body.len(), "mimeType": headers.get("content-type").and_then(|value| value.to_str().ok()).unwrap_or_default() });
+        if !status.is_success() {
+            content["text"] = Value::String(String::from_utf8_lossy(&body).into_owned());
+        }
+        let url = if self.user_id.is_empty() { url.to_string() } else { url.replace(&self.user_id, USER_ID) };
+        let time = time.as_millis() as u64;
+        let entry = json!({
+            "startedDateTime": started.to_rfc3339(),
+            "time": time,
+            "request": {
+                "method": method.as_str(),
+                "url": url,
+                "httpVersion": "HTTP/1.1",
+                "headers": [{ "name": "authorization", "value": "[redacted]" }],
+                "queryString": [],
+                "cookies": [],
+                "headersSize": -1,
+                "bodySize": 0,
+            },
+            "response": {
+                "status": status.as_u16(),
+                "statusText": status.canonical_reason().unwrap_or_default(),
+                "httpVersion": format!("{:?}", version),
+                "headers": recorded_headers,
+                "cookies": [],
+                "content": content,
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body.len(),
+            },
+            "cache": {},
+            "timings": { "send": 0, "wait": time, "receive": 0 },
+        });
+        self.entries.lock().expect("recorder lock poisoned.").push(entry);
+
+        let mut rebuilt = http::Response::builder().status(status).version(version);
+        for (name, value) in &headers {
+            rebuilt = rebuilt.header(name, value);
+        }
+        Ok(Response::from(rebuilt.body(body.to_vec())?))
+    }
+
+    fn write(&self) -> anyhow::Result<()> {
+        let entries = std::mem::take(&mut *self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        let count = entries.len();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "post_remove", "version": env!("CARGO_PKG_VERSION") },
+                "entries": entries,
+            },
+        });
+        crate::write_atomically(&self.path, &har)?;
+        println!("recorded session. path={} entries={}", self.path.display(), count);
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.write() {
+            eprintln!("failed to write recording. path={} err={:#}", self.path.display(), err);
+        }
+    }
+}
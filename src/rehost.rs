@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+use sha2::Sha256;
+use std::{env, fs, path::PathBuf};
+
+use crate::backup::sha256_hex;
+
+// RFC 3986 unreserved characters stay as they are in SigV4 canonical paths.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// where backed up media is copied so exports keep working after local cleanup.
+pub enum Rehost {
+    /// a directory served or synced elsewhere, published under `base_url`.
+    Dir { dir: PathBuf, base_url: String },
+    /// an S3-compatible bucket given as a path-style url, e.g. `https://s3.example.com/bucket`.
+    S3 { bucket_url: Url, base_url: Option<String>, region: String, access_key: String, secret_key: String },
+}
+
+impl Rehost {
+    /// parses `dir:<path>` or `s3:<bucket-url>`. S3 credentials come from the usual AWS_* variables.
+    pub fn parse(target: &str, base_url: Option<String>) -> Result<Self> {
+        if let Some(dir) = target.strip_prefix("dir:") {
+            let base_url = base_url.context("--rehost dir:... needs --rehost-base-url.")?;
+            return Ok(Rehost::Dir { dir: PathBuf::from(dir), base_url });
+        }
+        if let Some(bucket_url) = target.strip_prefix("s3:") {
+            let bucket_url = Url::parse(bucket_url).with_context(|| format!("invalid bucket url. url={}", bucket_url))?;
+            return Ok(Rehost::S3 {
+                bucket_url,
+                base_url,
+                region: env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key: env::var("AWS_ACCESS_KEY_ID").context("AWS_ACCESS_KEY_ID not found in environment.")?,
+                secret_key: env::var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY not found in environment.")?,
+            });
+        }
+        anyhow::bail!("unknown rehost target. expect dir:<path> or s3:<bucket-url>. target={}", target)
+    }
+
+    /// stores `bytes` under `key` and returns the url it can be viewed at.
+    pub async fn upload(&self, client: &reqwest::Client, key: &str, bytes: &[u8]) -> Result<String> {
+        match self {
+            Rehost::Dir { dir, base_url } => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).with_context(|| format!("failed to create {}.", parent.display()))?;
+                }
+                fs::write(&path, bytes).with_context(|| format!("failed to write {}.", path.display()))?;
+                Ok(format!("{}/{}", base_url.trim_end_matches('/'), key))
+            },
+            Rehost::S3 { bucket_url, base_url, region, access_key, secret_key } => {
+                let encoded_key: Vec<String> = key.split('/').map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string()).collect();
+                let path = format!("{}/{}", bucket_url.path().trim_end_matches('/'), encoded_key.join("/"));
+                let mut url = bucket_url.clone();
+                url.set_path(&path);
+                let host = match url.port() {
+                    Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                    None => url.host_str().unwrap_or_default().to_string(),
+                };
+
+                let now = Utc::now();
+                let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let payload_hash = sha256_hex(bytes);
+                let authorization = authorization(&path, &host, &payload_hash, &amz_date, region, access_key, secret_key);
+
+                client.put(url.clone())
+                    .header("x-amz-date", amz_date)
+                    .header("x-amz-content-sha256", payload_hash)
+                    .header("Authorization", authorization)
+                    .body(bytes.to_vec())
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .with_context(|| format!("upload rejected. url={}", url))?;
+                Ok(match base_url {
+                    Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), encoded_key.join("/")),
+                    None => url.to_string(),
+                })
+            },
+        }
+    }
+}
+
+/// the SigV4 Authorization header of a PUT to `path` on `host` of a body hashing to `payload_hash`.
+fn authorization(path: &str, host: &str, payload_hash: &str, amz_date: &str, region: &str, access_key: &str, secret_key: &str) -> String {
+    let day = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", day, region);
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        path, host, payload_hash, amz_date, signed_headers, payload_hash,
+    );
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical_request.as_bytes()));
+    let mut signing_key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), day.as_bytes());
+    for part in [region, "s3", "aws4_request"] {
+        signing_key = hmac_sha256(&signing_key, part.as_bytes());
+    }
+    let signature: String = hmac_sha256(&signing_key, string_to_sign.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}", access_key, scope, signed_headers, signature)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length.");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_sigv4() {
+        let key = utf8_percent_encode("a b.jpg", PATH_SEGMENT).to_string();
+        assert_eq!(key, "a%20b.jpg");
+        let payload_hash = sha256_hex(b"hello");
+        let authorization = authorization(
+            &format!("/bucket/media/1/{}", key), "s3.example.com:9000", &payload_hash, "20240102T030405Z",
+            "eu-west-1", "AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+        );
+        assert_eq!(authorization, "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240102/eu-west-1/s3/aws4_request, \
+            SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+            Signature=a3984282f462b0da0ea3f4bff0cad5a0fcc5bc2ae9202ca0ee1bd21ea6b7f9fe");
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, Response};
+use serde_json::Value;
+use std::{collections::VecDeque, fs, path::{Path, PathBuf}, sync::Mutex};
+
+/// the responses of a --record session, handed out in the order they were recorded.
+pub struct Replay {
+    path: PathBuf,
+    entries: Mutex<VecDeque<Value>>,
+}
+
+fn read(path: &Path) -> Result<Vec<Value>> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}.", path.display()))?;
+    let har: Value = serde_json::from_slice(&bytes).with_context(|| format!("failed to parse {}.", path.display()))?;
+    match har["log"]["entries"].as_array() {
+        Some(entries) => Ok(entries.clone()),
+        None => anyhow::bail!("not a recorded session. expect the HAR of --record. path={}", path.display()),
+    }
+}
+
+/// when the first recorded request was sent. a replay starts its clock there, so the reset
+/// timestamps of recorded 429s are as far ahead as they were.
+pub fn started_at(path: &Path) -> Option<DateTime<Utc>> {
+    let entries = read(path).ok()?;
+    let started = entries.first()?["startedDateTime"].as_str()?;
+    DateTime::parse_from_rfc3339(started).ok().map(|started| started.to_utc())
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self> {
+        let entries = read(path)?;
+        println!("replay. responses come from a recorded session. path={} entries={}", path.display(), entries.len());
+        Ok(Self { path: path.to_path_buf(), entries: Mutex::new(entries.into()) })
+    }
+
+    /// the next recorded response. one recorded for another request is still used, with a warning,
+    /// since a different input or flags send requests in another order.
+    pub fn next(&self, method: &Method, url: &str) -> Result<Response> {
+        let entry = self.entries.lock().expect("replay lock poisoned.").pop_front()
+            .with_context(|| format!("replay has no more responses. path={}", self.path.display()))?;
+        let (recorded_method, recorded_url) = (entry["request"]["method"].as_str().unwrap_or_default(), entry["request"]["url"].as_str().unwrap_or_default());
+        if recorded_method != method.as_str() || recorded_url != url {
+            eprintln!("{} replayed response was recorded for another request. recorded={} {} sent={} {}", crate::color::warning(), recorded_method, recorded_url, method, url);
+        }
+        let response = &entry["response"];
+        let mut builder = http::Response::builder().status(response["status"].as_u64().unwrap_or_default() as u16);
+        for header in response["headers"].as_array().into_iter().flatten() {
+            builder = builder.header(header["name"].as_str().unwrap_or_default(), header["value"].as_str().unwrap_or_default());
+        }
+        let body = response["content"]["text"].as_str().unwrap_or_default().to_string();
+        Ok(Response::from(builder.body(body).context("invalid recorded response.")?))
+    }
+}
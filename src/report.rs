@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use std::{collections::BTreeMap, fs::File, io::{BufRead, BufReader}, path::Path};
+
+use crate::{ledger::LedgerRecord, schema, text, timezone::{self, Zone}};
+
+const PREVIEW_CHARS: usize = 60;
+
+pub fn read_ledger(path: &Path) -> Result<Vec<LedgerRecord>> {
+    let file = File::open(path).with_context(|| format!("failed to open ledger. path={}", path.display()))?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut record: serde_json::Value = serde_json::from_str(&line)
+            .with_context(|| format!("invalid ledger line. path={} line={}", path.display(), index + 1))?;
+        schema::migrate_ledger_record(&mut record).with_context(|| format!("unsupported ledger line. path={} line={}", path.display(), index + 1))?;
+        let record = serde_json::from_value(record)
+            .with_context(|| format!("invalid ledger line. path={} line={}", path.display(), index + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn month(record: &LedgerRecord, zone: Zone) -> String {
+    timezone::parse(&record.created_at)
+        .map(|created_at| zone.format(created_at, "%Y-%m"))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn preview(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => text,
+    }
+}
+
+fn text(record: &LedgerRecord) -> String {
+    if record.redacted {
+        record.text.clone()
+    } else {
+        preview(&text::unescape_html(&record.text))
+    }
+}
+
+/// prints what a past run did, using only its ledger.
+pub fn print(records: &[LedgerRecord], top: usize, zone: Zone) {
+    let mut outcomes: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut months: BTreeMap<String, usize> = BTreeMap::new();
+    let mut runs: BTreeMap<&str, usize> = BTreeMap::new();
+    for record in records {
+        *runs.entry(record.run_id.as_deref().unwrap_or("unknown")).or_default() += 1;
+        *outcomes.entry(record.outcome.as_str()).or_default() += 1;
+        if record.outcome == "deleted" {
+            *months.entry(month(record, zone)).or_default() += 1;
+        }
+    }
+
+    println!("records: {}", records.len());
+    for (outcome, count) in &outcomes {
+        println!("  {}: {}", outcome, count);
+    }
+
+    // run ids start with the time, so this is oldest first.
+    println!("records per run:");
+    for (run_id, count) in &runs {
+        println!("  {}: {}", run_id, count);
+    }
+
+    println!("deleted per month:");
+    for (month, count) in &months {
+        println!("  {}: {}", month, count);
+    }
+
+    let mut deleted: Vec<&LedgerRecord> = records.iter().filter(|record| record.outcome == "deleted").collect();
+    deleted.sort_by_key(|record| std::cmp::Reverse(record.favorite_count + record.retweet_count));
+    println!("top {} deleted posts by engagement:", top.min(deleted.len()));
+    for record in deleted.iter().take(top) {
+        println!("  id={} likes={} retweets={} {} text={}",
+            record.id, record.favorite_count, record.retweet_count, zone.fields(&record.created_at), text(record));
+    }
+}
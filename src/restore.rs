@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::{collections::{BTreeMap, HashMap}, fs::File, path::{Path, PathBuf}};
+
+use crate::{backup::BackupRecord, export, report::preview, schema, text};
+
+#[derive(Serialize)]
+struct RepostPlan {
+    schema_version: u32,
+    created_at: chrono::DateTime<Utc>,
+    /// parents before their replies, so each reply can point at the new id of its parent.
+    posts: Vec<Repost>,
+}
+
+#[derive(Serialize)]
+struct Repost {
+    /// id of the deleted post.
+    id: u64,
+    created_at: Option<String>,
+    text: String,
+    /// id of the deleted parent when the parent is reposted too. the reply goes to its new copy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to: Option<u64>,
+    /// the backed up media files to attach.
+    media: Vec<PathBuf>,
+}
+
+/// the text to post again: t.co links are dead with the post, so they become the destinations
+/// the backup recorded, or are left out when there is none.
+fn repost_text(record: &BackupRecord) -> String {
+    let mut text = text::of(&record.tweet).to_string();
+    let media_urls: Vec<&str> = record.tweet["entities"]["media"].as_array().into_iter().flatten()
+        .filter_map(|media| media["url"].as_str())
+        .collect();
+    for link in &record.links {
+        // a media link would point back at the old post. the files are attached instead.
+        let replacement = link.expanded_url.as_deref().filter(|_| !media_urls.contains(&link.url.as_str())).unwrap_or_default();
+        text = text.replace(&link.url, replacement);
+    }
+    text::unescape_html(text.trim())
+}
+
+/// writes a repost plan for every backed up post whose text contains `pattern` (NFC, case folded),
+/// plus the backed up posts they reply to, so a thread comes back whole and in order.
+pub fn plan(records: &[BackupRecord], backup_dir: &Path, pattern: &str, out: &Path) -> Result<()> {
+    let by_id: HashMap<u64, &BackupRecord> = records.iter().map(|record| (record.id, record)).collect();
+    let pattern = text::normalize(pattern, true);
+    let mut selected: BTreeMap<u64, &BackupRecord> = BTreeMap::new();
+    let mut matched = 0;
+    for record in records.iter().filter(|record| text::normalize(&text::display(&record.tweet), true).contains(&pattern)) {
+        matched += 1;
+        let mut current = record;
+        // a post already selected has had its parents selected too, which also ends reply cycles.
+        while selected.insert(current.id, current).is_none() {
+            match export::parent(current).and_then(|parent| by_id.get(&parent)) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+    }
+
+    // ids grow with time, so id order puts every parent before its replies.
+    let posts: Vec<Repost> = selected.values()
+        .map(|record| Repost {
+            id: record.id,
+            created_at: record.tweet["created_at"].as_str().map(str::to_string),
+            text: repost_text(record),
+            reply_to: export::parent(record).filter(|parent| selected.contains_key(parent)),
+            media: record.media.iter().map(|media| backup_dir.join(record.id.to_string()).join(&media.file)).collect(),
+        })
+        .collect();
+    for post in &posts {
+        println!("repost. id={} reply_to={} text={}", post.id, post.reply_to.map(|id| id.to_string()).unwrap_or_default(), preview(&post.text));
+    }
+    let file = File::create(out).with_context(|| format!("failed to create {}.", out.display()))?;
+    let plan = RepostPlan { schema_version: schema::RESTORE, created_at: Utc::now(), posts };
+    serde_json::to_writer_pretty(file, &plan).with_context(|| format!("failed to write {}.", out.display()))?;
+    println!("restore plan written. path={} matched={} posts={}", out.display(), matched, plan.posts.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u64, reply_to: u64) -> BackupRecord {
+        BackupRecord {
+            schema_version: 0,
+            id,
+            backed_up_at: Utc::now(),
+            tweet: json!({"id_str": id.to_string(), "full_text": "thread", "in_reply_to_status_id_str": reply_to.to_string()}),
+            media: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn plan_ends_on_cycles() {
+        let records = vec![record(1, 1), record(2, 3), record(3, 2)];
+        let out = std::env::temp_dir().join(format!("restore-plan-{}.json", std::process::id()));
+        plan(&records, Path::new("backup"), "thread", &out).unwrap();
+        let written: serde_json::Value = serde_json::from_reader(File::open(&out).unwrap()).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(written["posts"].as_array().unwrap().len(), 3);
+    }
+}
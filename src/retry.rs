@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// statuses X answers with while a post is briefly locked, e.g. while its media is reprocessed.
+pub const TRANSIENT: [u16; 6] = [409, 423, 500, 502, 503, 504];
+
+pub struct Entry {
+    pub id: u64,
+    pub tweet: Value,
+    pub due: DateTime<Utc>,
+    /// how many times the post has been given back so far.
+    pub attempt: u32,
+}
+
+/// posts whose deletion failed transiently, tried again `after` later in the same run instead of
+/// being failed right away. a post is given back at most `attempts` times.
+pub struct RetryQueue {
+    after: chrono::Duration,
+    attempts: u32,
+    queue: VecDeque<Entry>,
+}
+
+impl RetryQueue {
+    pub fn new(after: chrono::Duration, attempts: u32) -> Self {
+        Self { after, attempts, queue: VecDeque::new() }
+    }
+
+    /// queues the next attempt of a post and returns when it's due. None once its attempts are used up.
+    pub fn push(&mut self, id: u64, tweet: &Value, attempt: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if attempt >= self.attempts {
+            return None;
+        }
+        let due = now + self.after;
+        // every entry waits the same time, so the queue stays in due order.
+        self.queue.push_back(Entry { id, tweet: tweet.clone(), due, attempt: attempt + 1 });
+        Some(due)
+    }
+
+    pub fn pop(&mut self) -> Option<Entry> {
+        self.queue.pop_front()
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+use crate::api::Action;
+
+/// which queue an entry waits in. likes go to a different endpoint, with a budget of its own.
+#[derive(Clone, Copy, PartialEq)]
+enum Queue {
+    Posts,
+    Likes,
+}
+
+/// hands out the entries of a batch. with likes and posts both pending, the next one comes from the
+/// queue whose endpoint has more of its window left, so a used up budget on one side doesn't hold up
+/// the other. each queue keeps its own order.
+pub struct Schedule<'a> {
+    posts: VecDeque<&'a Value>,
+    likes: VecDeque<&'a Value>,
+    last: Option<Queue>,
+}
+
+impl<'a> Schedule<'a> {
+    pub fn new(batch: &'a [Value]) -> Self {
+        let (likes, posts) = batch.iter().partition(|entry| matches!(Action::of(entry), Action::Unlike));
+        Self { posts, likes, last: None }
+    }
+
+    /// whether both kinds are pending, so what is picked makes a difference.
+    pub fn is_mixed(&self) -> bool {
+        !self.posts.is_empty() && !self.likes.is_empty()
+    }
+
+    /// the next entry. `left` is what the window of an action has left at `now`, and `ready` whether
+    /// a post may go yet (--daemon waits for its expiry). a post that isn't ready only comes out
+    /// when no like is left to send instead.
+    pub fn next(&mut self, now: DateTime<Utc>, left: impl Fn(Action, DateTime<Utc>) -> u64, ready: impl Fn(&Value) -> bool) -> Option<&'a Value> {
+        let queue = match (self.posts.front(), self.likes.front()) {
+            (None, None) => return None,
+            (Some(_), None) => Queue::Posts,
+            (None, Some(_)) => Queue::Likes,
+            (Some(post), Some(_)) if !ready(post) => Queue::Likes,
+            (Some(post), Some(like)) => {
+                let (posts_left, likes_left) = (left(Action::of(post), now), left(Action::of(like), now));
+                if posts_left != likes_left {
+                    if posts_left > likes_left { Queue::Posts } else { Queue::Likes }
+                } else if self.last == Some(Queue::Posts) {
+                    Queue::Likes
+                } else {
+                    Queue::Posts
+                }
+            },
+        };
+        self.last = Some(queue);
+        match queue {
+            Queue::Posts => self.posts.pop_front(),
+            Queue::Likes => self.likes.pop_front(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post(id: u64) -> Value {
+        json!({ "tweet": { "id": id.to_string(), "full_text": "post" } })
+    }
+
+    fn like(id: u64) -> Value {
+        json!({ "like": { "tweetId": id.to_string() } })
+    }
+
+    fn order(batch: &[Value], left: impl Fn(Action) -> u64, ready: impl Fn(&Value) -> bool) -> Vec<String> {
+        let mut schedule = Schedule::new(batch);
+        let now = Utc::now();
+        std::iter::from_fn(|| schedule.next(now, |action, _| left(action), &ready))
+            .map(|entry| if entry["like"].is_object() { format!("like {}", entry["like"]["tweetId"].as_str().unwrap()) } else { format!("post {}", entry["tweet"]["id"].as_str().unwrap()) })
+            .collect()
+    }
+
+    #[test]
+    fn alternates_while_the_windows_are_even() {
+        let batch = [post(1), post(2), like(3), like(4)];
+        assert_eq!(order(&batch, |_| 10, |_| true), ["post 1", "like 3", "post 2", "like 4"]);
+    }
+
+    #[test]
+    fn prefers_the_window_with_more_left() {
+        let batch = [post(1), post(2), like(3)];
+        let left = |action| if matches!(action, Action::Unlike) { 0 } else { 10 };
+        assert_eq!(order(&batch, left, |_| true), ["post 1", "post 2", "like 3"]);
+    }
+
+    #[test]
+    fn posts_that_are_not_ready_let_likes_go_first() {
+        let batch = [post(1), like(2), like(3)];
+        assert_eq!(order(&batch, |_| 10, |entry| entry["tweet"].is_null()), ["like 2", "like 3", "post 1"]);
+    }
+}
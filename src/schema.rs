@@ -0,0 +1,55 @@
+use anyhow::Result;
+use serde_json::Value;
+
+// versions of the files the tool writes. files from before versioning count as version 0.
+// readers migrate older files up to the current version before deserializing them, and refuse
+// files written by a newer release instead of misreading them.
+pub const STATE: u32 = 1;
+pub const LEDGER: u32 = 1;
+pub const BACKUP: u32 = 1;
+pub const PROGRESS: u32 = 1;
+pub const RESTORE: u32 = 1;
+#[cfg(feature = "x-v2")]
+pub const OAUTH2_TOKEN: u32 = 1;
+pub const APPROVAL: u32 = 1;
+pub const SUMMARY: u32 = 1;
+pub const AUDIT: u32 = 1;
+pub const CONFIG: u32 = 1;
+pub const MANUAL: u32 = 1;
+
+fn version(value: &Value) -> u32 {
+    value["schema_version"].as_u64().map_or(0, |version| version as u32)
+}
+
+pub fn ensure_supported(kind: &str, found: u32, current: u32) -> Result<()> {
+    anyhow::ensure!(found <= current, "{} was written by a newer post_remove. upgrade to read it. schema_version={} supported={}", kind, found, current);
+    Ok(())
+}
+
+/// the metadata object of a remaining or plan file.
+pub fn migrate_metadata(metadata: &mut Value) -> Result<()> {
+    let found = version(metadata);
+    ensure_supported("state file", found, STATE)?;
+    if found < 1 {
+        // the failed counter came after the first metadata.
+        if metadata.get("failed").is_none() {
+            metadata["failed"] = Value::from(0);
+        }
+    }
+    metadata["schema_version"] = Value::from(STATE);
+    Ok(())
+}
+
+/// one line of a ledger.
+pub fn migrate_ledger_record(record: &mut Value) -> Result<()> {
+    ensure_supported("ledger", version(record), LEDGER)?;
+    record["schema_version"] = Value::from(LEDGER);
+    Ok(())
+}
+
+/// `post.json` of a backup.
+pub fn migrate_backup_record(record: &mut Value) -> Result<()> {
+    ensure_supported("backup", version(record), BACKUP)?;
+    record["schema_version"] = Value::from(BACKUP);
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use chrono::Utc;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use crate::Credentials;
+
+// RFC 3986 unreserved characters, the only ones OAuth 1.0a leaves unencoded.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, UNRESERVED).to_string()
+}
+
+/// enough of a key to tell which one was used, never the whole of it.
+fn redact(value: &str) -> String {
+    let prefix: String = value.chars().take(4).collect();
+    format!("{}…(len={})", prefix, value.chars().count())
+}
+
+/// the oauth_* parameters of an `OAuth k="v", ...` header, decoded.
+fn header_params(authorization: &str) -> Vec<(String, String)> {
+    authorization.trim_start_matches("OAuth ").split(", ")
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), percent_decode_str(value.trim_matches('"')).decode_utf8_lossy().into_owned()))
+        .collect()
+}
+
+/// where --debug-auth writes: stderr, or appended to a file so it can be compared across runs.
+pub struct DebugAuth(pub Option<PathBuf>);
+
+impl DebugAuth {
+    /// records the signature base string of a rejected request, rebuilt from the header it was sent
+    /// with, plus whether the secrets look sane. key values are cut to a prefix.
+    pub fn rejected(&self, credentials: &Credentials, method: &str, url: &str, authorization: &str, params: &[(&str, &str)], status: u16) {
+        let mut normalized: Vec<(String, String)> = header_params(authorization).into_iter()
+            .filter(|(key, _)| key != "oauth_signature")
+            .chain(params.iter().map(|(key, value)| (key.to_string(), value.to_string())))
+            .map(|(key, value)| (encode(&key), encode(&value)))
+            .collect();
+        normalized.sort();
+        let query = normalized.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("&");
+        let base = format!("{}&{}&{}", encode(method), encode(url), encode(&query));
+        let redacted = base
+            .replace(&encode(&encode(&credentials.consumer_key)), &redact(&credentials.consumer_key))
+            .replace(&encode(&encode(&credentials.access_key)), &redact(&credentials.access_key));
+        let secrets = [("consumer_secret", &credentials.consumer_secret), ("access_secret", &credentials.access_secret)].iter()
+            .map(|(name, secret)| format!("{}=(len={} whitespace={})", name, secret.len(), secret.trim() != secret.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut report = format!("auth debug. at={} status={} method={} url={}\n", Utc::now().to_rfc3339(), status, method, url);
+        for (key, value) in &normalized {
+            let value = if *key == "oauth_consumer_key" || *key == "oauth_token" { redact(value) } else { value.clone() };
+            report += &format!("  param {}={}\n", key, value);
+        }
+        report += &format!("  base string: {}\n", redacted);
+        report += &format!("  signing key: {}\n", secrets);
+        match &self.0 {
+            Some(path) => {
+                let written = OpenOptions::new().create(true).append(true).open(path)
+                    .and_then(|mut file| file.write_all(report.as_bytes()));
+                if let Err(err) = written {
+                    eprintln!("{} failed to write auth debug. path={} err={}", crate::color::warning(), path.display(), err);
+                }
+            },
+            None => eprint!("{}", report),
+        }
+    }
+}
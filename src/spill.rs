@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{fs::{self, File}, io::{BufRead, BufReader, BufWriter, Write}, path::PathBuf, sync::atomic::{AtomicUsize, Ordering}};
+
+/// entries on their way through a run, kept in memory while they fit `budget` bytes and in a
+/// temporary file once they don't. the size is that of the entries as JSON, which is roughly what
+/// they take in memory too.
+pub struct Spool {
+    budget: Option<u64>,
+    used: u64,
+    len: usize,
+    memory: Vec<Value>,
+    file: Option<(TempFile, BufWriter<File>)>,
+}
+
+/// removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create() -> Result<(Self, File)> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("post_remove-{}-{}.jsonl", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed)));
+        let file = File::create(&path).with_context(|| format!("failed to create spill file. path={}", path.display()))?;
+        Ok((Self(path), file))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+impl Spool {
+    /// without a budget everything stays in memory.
+    pub fn new(budget: Option<u64>) -> Self {
+        Self { budget, used: 0, len: 0, memory: Vec::new(), file: None }
+    }
+
+    pub fn push(&mut self, entry: Value) -> Result<()> {
+        self.len += 1;
+        if let Some((_, writer)) = &mut self.file {
+            serde_json::to_writer(&mut *writer, &entry)?;
+            writeln!(writer)?;
+            return Ok(());
+        }
+        self.used += size_of(&entry);
+        self.memory.push(entry);
+        if self.budget.is_some_and(|budget| self.used > budget) {
+            let (file, handle) = TempFile::create()?;
+            let mut writer = BufWriter::new(handle);
+            for entry in self.memory.drain(..) {
+                serde_json::to_writer(&mut writer, &entry)?;
+                writeln!(writer)?;
+            }
+            self.memory.shrink_to_fit();
+            self.file = Some((file, writer));
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// the entries in order, in runs of at most `chunk` bytes, or all at once while in memory.
+    pub fn into_chunks(self, chunk: u64) -> Result<Chunks> {
+        let source = match self.file {
+            None => Source::Memory(Some(self.memory)),
+            Some((file, writer)) => {
+                writer.into_inner().map_err(|err| err.into_error())?;
+                let reader = File::open(&file.0).with_context(|| format!("failed to read spill file. path={}", file.0.display()))?;
+                Source::Disk { lines: BufReader::new(reader).lines(), _file: file }
+            },
+        };
+        Ok(Chunks { source, chunk: chunk.max(1) })
+    }
+
+    /// the entries one by one, in order.
+    pub fn into_entries(self) -> Result<impl Iterator<Item = Value>> {
+        Ok(self.into_chunks(u64::MAX)?.flat_map(|chunk| chunk.unwrap_or_else(|err| {
+            eprintln!("failed to read spill file. err={:#}", err);
+            Vec::new()
+        })))
+    }
+}
+
+fn size_of(entry: &Value) -> u64 {
+    serde_json::to_vec(entry).map_or(0, |bytes| bytes.len() as u64)
+}
+
+enum Source {
+    Memory(Option<Vec<Value>>),
+    /// the file goes once the lines are read.
+    Disk { lines: std::io::Lines<BufReader<File>>, _file: TempFile },
+}
+
+pub struct Chunks {
+    source: Source,
+    chunk: u64,
+}
+
+impl Iterator for Chunks {
+    type Item = Result<Vec<Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lines = match &mut self.source {
+            Source::Memory(entries) => return entries.take().filter(|entries| !entries.is_empty()).map(Ok),
+            Source::Disk { lines, .. } => lines,
+        };
+        let (mut entries, mut used) = (Vec::new(), 0);
+        while used < self.chunk {
+            let Some(line) = lines.next() else { break };
+            let parsed = line.map_err(anyhow::Error::from).and_then(|line| {
+                used += line.len() as u64;
+                serde_json::from_str(&line).context("invalid spill file.")
+            });
+            match parsed {
+                Ok(entry) => entries.push(entry),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        (!entries.is_empty()).then_some(Ok(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries(count: u64) -> Vec<Value> {
+        (0..count).map(|id| json!({ "tweet": { "id": id.to_string() } })).collect()
+    }
+
+    #[test]
+    fn stays_in_memory_within_the_budget() {
+        let mut spool = Spool::new(Some(1 << 20));
+        entries(10).into_iter().try_for_each(|entry| spool.push(entry)).unwrap();
+        assert!(!spool.is_spilled());
+        assert_eq!(spool.into_entries().unwrap().collect::<Vec<_>>(), entries(10));
+    }
+
+    #[test]
+    fn spills_past_the_budget_and_reads_back_in_order() {
+        let mut spool = Spool::new(Some(100));
+        entries(50).into_iter().try_for_each(|entry| spool.push(entry)).unwrap();
+        assert!(spool.is_spilled());
+        assert_eq!(spool.len(), 50);
+        let TempFile(path) = &spool.file.as_ref().unwrap().0;
+        let path = path.clone();
+        let chunks: Vec<Vec<Value>> = spool.into_chunks(200).unwrap().collect::<Result<_>>().unwrap();
+        // each entry is 20 bytes or so, so 200 bytes hold about ten.
+        assert!(chunks.len() > 1 && chunks.iter().all(|chunk| chunk.len() <= 10));
+        assert_eq!(chunks.concat(), entries(50));
+        assert!(!path.exists());
+    }
+}
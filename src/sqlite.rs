@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use rusqlite::{params, Connection, OpenFlags};
+use serde_json::Value;
+use std::{collections::HashSet, path::Path};
+
+const SCHEMA: &str = "
+CREATE TABLE posts (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    full_text TEXT NOT NULL,
+    favorite_count INTEGER NOT NULL,
+    retweet_count INTEGER NOT NULL,
+    in_reply_to_status_id INTEGER,
+    in_reply_to_screen_name TEXT,
+    is_retweet INTEGER NOT NULL,
+    lang TEXT,
+    source TEXT,
+    raw TEXT NOT NULL
+);
+CREATE TABLE entities (
+    post_id INTEGER NOT NULL REFERENCES posts(id),
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE media (
+    post_id INTEGER NOT NULL REFERENCES posts(id),
+    media_id INTEGER,
+    type TEXT,
+    media_url TEXT,
+    expanded_url TEXT
+);
+CREATE INDEX entities_post_id ON entities(post_id);
+CREATE INDEX media_post_id ON media(post_id);
+";
+
+fn number(value: &Value) -> Option<i64> {
+    value.as_str().and_then(|number| number.parse().ok()).or_else(|| value.as_i64())
+}
+
+/// creates the tables and inserts every archive entry. created_at is stored as RFC 3339 so it sorts.
+pub fn load(conn: &mut Connection, entries: &[Value]) -> Result<()> {
+    conn.execute_batch(SCHEMA)?;
+    let transaction = conn.transaction()?;
+    {
+        let mut post = transaction.prepare(
+            "INSERT OR IGNORE INTO posts (id, created_at, full_text, favorite_count, retweet_count, in_reply_to_status_id,
+                in_reply_to_screen_name, is_retweet, lang, source, raw) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)")?;
+        let mut entity = transaction.prepare("INSERT INTO entities (post_id, kind, value) VALUES (?1, ?2, ?3)")?;
+        let mut media = transaction.prepare(
+            "INSERT INTO media (post_id, media_id, type, media_url, expanded_url) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for entry in entries {
+            let tweet = &entry["tweet"];
+            let Some(id) = number(&tweet["id"]) else {
+                continue;
+            };
+            let created_at = tweet["created_at"].as_str()
+                .and_then(|created_at| DateTime::parse_from_str(created_at, "%a %b %d %H:%M:%S %z %Y").ok())
+                .map(|created_at| created_at.to_utc().to_rfc3339())
+                .unwrap_or_default();
+            let full_text = crate::text::of(tweet);
+            let inserted = post.execute(params![
+                id,
+                created_at,
+                full_text,
+                number(&tweet["favorite_count"]).unwrap_or(0),
+                number(&tweet["retweet_count"]).unwrap_or(0),
+                number(&tweet["in_reply_to_status_id_str"]).or_else(|| number(&tweet["in_reply_to_status_id"])),
+                tweet["in_reply_to_screen_name"].as_str(),
+                full_text.starts_with("RT @"),
+                tweet["lang"].as_str(),
+                tweet["source"].as_str(),
+                tweet.to_string(),
+            ])?;
+            // a post listed twice, e.g. by overlapping archive parts, keeps its first copy and its entities once.
+            if inserted == 0 {
+                continue;
+            }
+
+            let entities = &tweet["entities"];
+            for (kind, list, key) in [("hashtag", "hashtags", "text"), ("mention", "user_mentions", "screen_name"), ("url", "urls", "expanded_url")] {
+                for item in entities[list].as_array().into_iter().flatten() {
+                    if let Some(value) = item[key].as_str() {
+                        entity.execute(params![id, kind, value])?;
+                    }
+                }
+            }
+            let items = tweet["extended_entities"]["media"].as_array().or_else(|| entities["media"].as_array());
+            for item in items.into_iter().flatten() {
+                media.execute(params![
+                    id,
+                    number(&item["id_str"]).or_else(|| number(&item["id"])),
+                    item["type"].as_str(),
+                    item["media_url_https"].as_str(),
+                    item["expanded_url"].as_str(),
+                ])?;
+            }
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+pub fn export(entries: &[Value], db: &Path) -> Result<()> {
+    anyhow::ensure!(!db.exists(), "database already exists. path={}", db.display());
+    let mut conn = Connection::open(db).with_context(|| format!("failed to open {}.", db.display()))?;
+    load(&mut conn, entries)?;
+    println!("exported. format=sqlite posts={} out={}", entries.len(), db.display());
+    Ok(())
+}
+
+/// runs `query` and collects the first column as post ids. without `db` the query runs against
+/// an in-memory copy of the archive with the same tables as `export sqlite`.
+pub fn select_ids(query: &str, db: Option<&Path>, entries: &[Value]) -> Result<HashSet<u64>> {
+    let conn = match db {
+        Some(db) => Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("failed to open {}.", db.display()))?,
+        None => {
+            let mut conn = Connection::open_in_memory()?;
+            load(&mut conn, entries)?;
+            conn
+        },
+    };
+    let mut statement = conn.prepare(query).with_context(|| format!("invalid query. query={}", query))?;
+    let ids = statement.query_map([], |row| row.get::<_, i64>(0))?
+        .map(|id| id.map(|id| id as u64))
+        .collect::<rusqlite::Result<HashSet<u64>>>()
+        .context("the first column of --select-sql must be a post id.")?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn loads_a_repeated_post_once() {
+        let tweet = json!({"tweet": {"id": "1", "full_text": "hello", "entities": {"hashtags": [{"text": "a"}]}}});
+        let mut conn = Connection::open_in_memory().unwrap();
+        load(&mut conn, &[tweet.clone(), tweet]).unwrap();
+        let posts: i64 = conn.query_row("SELECT count(*) FROM posts", [], |row| row.get(0)).unwrap();
+        assert_eq!(posts, 1);
+        let entities: i64 = conn.query_row("SELECT count(*) FROM entities", [], |row| row.get(0)).unwrap();
+        assert_eq!(entities, 1);
+    }
+}
@@ -0,0 +1,72 @@
+use std::{io::{self, BufRead, IsTerminal}, sync::{Arc, Mutex, Once}, time::Instant};
+
+/// what the run is doing right now, for the on-demand status dump.
+#[derive(Default)]
+pub struct Status {
+    pub run_id: String,
+    pub current: Option<u64>,
+    pub processed: usize,
+    pub remaining: usize,
+    pub failed: usize,
+    /// x-user-limit-24hour-remaining, once a response had it.
+    pub daily_remaining: Option<u64>,
+    /// reason and start of the rate-limit wait in progress.
+    pub waiting: Option<(String, Instant)>,
+    pub last_error: Option<String>,
+}
+
+impl Status {
+    fn print(&self) {
+        let current = self.current.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string());
+        let waiting = match &self.waiting {
+            Some((reason, since)) => format!("{} for {}s", reason, since.elapsed().as_secs()),
+            None => "-".to_string(),
+        };
+        let daily_remaining = self.daily_remaining.map(|remaining| remaining.to_string()).unwrap_or_else(|| "-".to_string());
+        println!("status. run_id={} current={} processed={} remaining={} failed={} daily_remaining={} waiting={} last_error={}",
+            self.run_id, current, self.processed, self.remaining, self.failed, daily_remaining, waiting, self.last_error.as_deref().unwrap_or("-"));
+    }
+}
+
+pub type SharedStatus = Arc<Mutex<Status>>;
+
+/// what the listeners print. they're started once per process, and each run or listing before
+/// it puts its own status here.
+static CURRENT: Mutex<Option<SharedStatus>> = Mutex::new(None);
+
+fn dump() {
+    let current = CURRENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    if let Some(status) = current {
+        status.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).print();
+    }
+}
+
+/// prints `status` on SIGUSR2, and when `s` is typed followed by Enter on an interactive terminal.
+pub fn listen(status: SharedStatus) {
+    static STARTED: Once = Once::new();
+    *CURRENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(status);
+    STARTED.call_once(|| {
+        #[cfg(unix)]
+        tokio::spawn(async move {
+            let Ok(mut signal) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined2()) else {
+                eprintln!("failed to listen for SIGUSR2.");
+                return;
+            };
+            while signal.recv().await.is_some() {
+                dump();
+            }
+        });
+        if io::stdin().is_terminal() {
+            // a plain thread, since a blocking stdin read would otherwise hold up the runtime on exit.
+            std::thread::spawn(move || {
+                for line in io::stdin().lock().lines() {
+                    match line {
+                        Ok(line) if line.trim() == "s" => dump(),
+                        Ok(_) => {},
+                        Err(_) => break,
+                    }
+                }
+            });
+        }
+    });
+}
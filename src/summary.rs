@@ -0,0 +1,77 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{path::Path, sync::Mutex, time::Duration};
+
+/// the longest rate-limit wait of a run so far, and what it waited for.
+#[derive(Default)]
+pub struct Waits {
+    longest: Mutex<Option<(Duration, String)>>,
+}
+
+impl Waits {
+    pub fn record(&self, duration: Duration, reason: &str) {
+        let mut longest = self.longest.lock().expect("waits lock poisoned.");
+        if longest.as_ref().is_none_or(|(longest, _)| duration > *longest) {
+            *longest = Some((duration, reason.to_string()));
+        }
+    }
+
+    fn longest(&self) -> Option<(Duration, String)> {
+        self.longest.lock().expect("waits lock poisoned.").clone()
+    }
+}
+
+/// what a run did, printed when it ends and written by --report.
+#[derive(Default, Serialize)]
+pub struct Summary {
+    pub schema_version: u32,
+    pub run_id: String,
+    pub deleted: usize,
+    pub not_found: usize,
+    /// marked to keep in the web ui.
+    pub kept: usize,
+    /// entries of the input the selection left out.
+    pub skipped: usize,
+    pub failed: usize,
+    pub remaining: usize,
+    /// stopped by Ctrl+C or the web ui before every candidate was processed.
+    pub stopped: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_seconds: i64,
+    pub longest_wait_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longest_wait_reason: Option<String>,
+}
+
+impl Summary {
+    pub fn new(run_id: &str, started_at: DateTime<Utc>) -> Self {
+        Self { schema_version: crate::schema::SUMMARY, run_id: run_id.to_string(), started_at, ..Self::default() }
+    }
+
+    pub fn finish(&mut self, finished_at: DateTime<Utc>, waits: &Waits) {
+        self.finished_at = finished_at;
+        self.duration_seconds = (finished_at - self.started_at).num_seconds();
+        if let Some((duration, reason)) = waits.longest() {
+            self.longest_wait_seconds = duration.as_secs();
+            self.longest_wait_reason = Some(reason);
+        }
+    }
+
+    pub fn print(&self, simulated: bool) {
+        let took = crate::format_remaining(Duration::from_secs(self.duration_seconds.max(0) as u64));
+        let longest_wait = match &self.longest_wait_reason {
+            Some(reason) => format!("{} ({})", crate::format_remaining(Duration::from_secs(self.longest_wait_seconds)), reason),
+            None => "-".to_string(),
+        };
+        println!("summary. run_id={} deleted={} not_found={} kept={} skipped={} failed={} remaining={} took={}{} longest_wait={}",
+            self.run_id, self.deleted, self.not_found, self.kept, self.skipped, self.failed, self.remaining, took, if simulated { " (simulated)" } else { "" }, longest_wait);
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        crate::write_atomically(path, &serde_json::to_value(self)?)?;
+        println!("wrote report. path={}", path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::{env, ffi::OsString, fs, path::{Path, PathBuf}};
+
+/// flags that only make sense when generating the units, so they are dropped from ExecStart.
+const SERVICE_FLAGS: [&str; 4] = ["--install-service", "--print-service", "--schedule", "--service-dir"];
+const SERVICE_FLAGS_WITH_VALUE: [&str; 2] = ["--schedule", "--service-dir"];
+
+/// quotes one argument for ExecStart. `%` and `$` are specifiers in unit files.
+fn quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if escaped.is_empty() || escaped.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        escaped
+    }
+}
+
+/// the command line `args` minus the service flags, plus the flags needed to run unattended.
+fn exec_start(args: Vec<OsString>) -> Result<String> {
+    let exe = env::current_exe().context("failed to resolve current executable.")?;
+    let mut parts = vec![quote(&exe.to_string_lossy())];
+    let mut args = args.into_iter().skip(1).map(|arg| arg.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        let name = arg.split('=').next().unwrap_or_default();
+        if SERVICE_FLAGS.contains(&name) {
+            if SERVICE_FLAGS_WITH_VALUE.contains(&name) && !arg.contains('=') {
+                args.next();
+            }
+            continue;
+        }
+        if arg == "--yes" || arg == "-y" || arg == "--non-interactive" {
+            continue;
+        }
+        parts.push(quote(&arg));
+    }
+    parts.push("--yes".to_string());
+    parts.push("--non-interactive".to_string());
+    Ok(parts.join(" "))
+}
+
+pub fn service_unit(args: Vec<OsString>) -> Result<String> {
+    let workdir = env::current_dir().context("failed to resolve working directory.")?;
+    Ok(format!(
+        "[Unit]\n\
+         Description=post_remove rolling retention\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=notify\n\
+         NotifyAccess=main\n\
+         WorkingDirectory={}\n\
+         ExecStart={}\n",
+        quote(&workdir.to_string_lossy()),
+        exec_start(args)?,
+    ))
+}
+
+pub fn timer_unit(schedule: &str) -> String {
+    format!(
+        "[Unit]\n\
+         Description=run post_remove on schedule\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        schedule,
+    )
+}
+
+/// `~/.config/systemd/user`, where `systemctl --user` looks for units.
+pub fn default_dir() -> Result<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) => PathBuf::from(config),
+        None => PathBuf::from(env::var_os("HOME").context("HOME not found in environment.")?).join(".config"),
+    };
+    Ok(config.join("systemd").join("user"))
+}
+
+pub fn install(dir: &Path, service: &str, timer: &str) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}.", dir.display()))?;
+    for (name, content) in [("post_remove.service", service), ("post_remove.timer", timer)] {
+        let path = dir.join(name);
+        fs::write(&path, content).with_context(|| format!("failed to write {}.", path.display()))?;
+        println!("installed. path={}", path.display());
+    }
+    println!("enable with: systemctl --user daemon-reload && systemctl --user enable --now post_remove.timer");
+    Ok(())
+}
+
+/// sends a state line (`READY=1`, `STATUS=...`) to the service manager. no-op outside systemd.
+#[cfg(unix)]
+pub fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(socket_path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let Ok(socket) = UnixDatagram::unbound() else {
+        return;
+    };
+    let socket_path = socket_path.to_string_lossy().into_owned();
+    let result = match socket_path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            std::os::unix::net::SocketAddr::from_abstract_name(name)
+                .and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+        },
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => return,
+        None => socket.send_to(state.as_bytes(), &socket_path),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to notify systemd. err={}", err);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) {}
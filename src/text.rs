@@ -0,0 +1,100 @@
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+
+/// text as every text filter compares it: NFC-normalized, and Unicode case folded with `ignore_case`.
+/// composed and decomposed kana, or full-width and ASCII letters under folding, then match.
+pub fn normalize(text: &str, ignore_case: bool) -> String {
+    let composed: String = text.nfc().collect();
+    if ignore_case {
+        // folding can produce decomposed sequences again.
+        caseless::default_case_fold_str(&composed).nfc().collect()
+    } else {
+        composed
+    }
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, flags, skin tones
+        | 0x2600..=0x27BF // misc symbols and dingbats
+        | 0x2B00..=0x2BFF // arrows, stars
+        | 0x2190..=0x21FF
+        | 0x2300..=0x23FF
+        | 0xFE00..=0xFE0F // variation selectors
+        | 0x200D // zero width joiner
+        | 0x20E3 // keycap
+        | 0xE0020..=0xE007F // tag sequences
+    )
+}
+
+/// drops URLs, @mentions and emoji, so what's left is the text the user actually wrote.
+pub fn strip_entities(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| !word.starts_with("http://") && !word.starts_with("https://"))
+        .filter(|word| !(word.starts_with('@') && word.len() > 1))
+        .map(|word| word.chars().filter(|c| !is_emoji(*c)).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the text filters work on. see [`normalize`] and [`strip_entities`].
+pub fn comparable(text: &str, ignore_case: bool, strip: bool) -> String {
+    if strip {
+        normalize(&strip_entities(text), ignore_case)
+    } else {
+        normalize(text, ignore_case)
+    }
+}
+
+/// a --match or --exclude pattern brought to the form of [`comparable`] text: NFC-normalized, and case
+/// folded with `ignore_case`. the letter after a backslash is left alone, since `\S` and `\s` differ.
+pub fn comparable_pattern(pattern: &str, ignore_case: bool) -> String {
+    let composed: String = pattern.nfc().collect();
+    if !ignore_case {
+        return composed;
+    }
+    let mut folded = String::with_capacity(composed.len());
+    let mut escaped = false;
+    for c in composed.chars() {
+        if escaped || c == '\\' {
+            folded.push(c);
+        } else {
+            folded.push_str(&caseless::default_case_fold_str(c.encode_utf8(&mut [0; 4])));
+        }
+        escaped = !escaped && c == '\\';
+    }
+    folded.nfc().collect()
+}
+
+/// `full_text` of a post (the `tweet` object), or `text` for entries that only have that, truncated
+/// as it may be.
+pub fn of(tweet: &Value) -> &str {
+    tweet["full_text"].as_str().or_else(|| tweet["text"].as_str()).unwrap_or_default()
+}
+
+/// the archive keeps text HTML-escaped, as the API returns it.
+pub fn unescape_html(text: &str) -> String {
+    // &amp; last, so "&amp;lt;" becomes "&lt;" and not "<".
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'").replace("&amp;", "&")
+}
+
+/// the text of a post (the `tweet` object) for people to read: t.co links replaced with the
+/// display url of their entity (e.g. example.com/page…) and HTML entities unescaped.
+pub fn display(tweet: &Value) -> String {
+    let mut text = of(tweet).to_string();
+    let entities = tweet["entities"]["urls"].as_array().into_iter().flatten()
+        .chain(tweet["entities"]["media"].as_array().into_iter().flatten());
+    for entity in entities {
+        if let (Some(url), Some(display_url)) = (entity["url"].as_str(), entity["display_url"].as_str()) {
+            text = text.replace(url, display_url);
+        }
+    }
+    unescape_html(&text)
+}
+
+/// stands in for the text of a post in artifacts handed to others: whoever has the post can check
+/// it's the same one, nobody else learns what it said.
+pub fn redact(full_text: &str) -> String {
+    format!("sha256:{} len={}", crate::backup::sha256_hex(full_text.as_bytes()), full_text.chars().count())
+}
@@ -0,0 +1,190 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+pub fn id(tweet: &Value) -> Option<u64> {
+    tweet["tweet"]["id"].as_str().and_then(|id| id.parse().ok())
+}
+
+pub fn parent_id(tweet: &Value) -> Option<u64> {
+    tweet["tweet"]["in_reply_to_status_id_str"].as_str()
+        .or_else(|| tweet["tweet"]["in_reply_to_status_id"].as_str())
+        .and_then(|id| id.parse().ok())
+}
+
+/// reply graph over a set of posts. a reply whose parent is also in the archive is a self-reply.
+struct Graph<'a> {
+    posts: HashMap<u64, &'a Value>,
+    children: HashMap<u64, Vec<u64>>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(posts: &'a [Value]) -> Self {
+        let posts: HashMap<u64, &Value> = posts.iter().filter_map(|tweet| id(tweet).map(|id| (id, tweet))).collect();
+        let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+        for (id, tweet) in &posts {
+            if let Some(parent) = parent_id(tweet).filter(|parent| posts.contains_key(parent)) {
+                children.entry(parent).or_default().push(*id);
+            }
+        }
+        // newest reply first, so the deepest branches go before older siblings.
+        for replies in children.values_mut() {
+            replies.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        Self { posts, children }
+    }
+
+    /// `root` and everything below it, every reply before the post it replies to. posts already in
+    /// `visited` are left out, which also ends self-replies and reply cycles.
+    fn bottom_up(&self, root: u64, visited: &mut HashSet<u64>, ordered: &mut Vec<Value>) {
+        // iterative post-order, threads can be thousands of posts deep.
+        let mut stack = vec![(root, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                if let Some(tweet) = self.posts.get(&id) {
+                    ordered.push((*tweet).clone());
+                }
+                continue;
+            }
+            if !visited.insert(id) {
+                continue;
+            }
+            stack.push((id, true));
+            for child in self.children.get(&id).into_iter().flatten().rev() {
+                stack.push((*child, false));
+            }
+        }
+    }
+}
+
+/// the self-reply chain rooted at `root`, replies before roots. `None` when `root` isn't in the archive.
+pub fn thread(entries: &[Value], root: u64) -> Option<Vec<Value>> {
+    let graph = Graph::new(entries);
+    if !graph.posts.contains_key(&root) {
+        return None;
+    }
+    let mut ordered = Vec::new();
+    graph.bottom_up(root, &mut HashSet::new(), &mut ordered);
+    Some(ordered)
+}
+
+/// whether the post replies to or mentions one of `users` (lowercase, without the @).
+fn involves(tweet: &Value, users: &[String]) -> bool {
+    let tweet = &tweet["tweet"];
+    let replied_to = tweet["in_reply_to_screen_name"].as_str().into_iter();
+    let mentioned = tweet["entities"]["user_mentions"].as_array().into_iter().flatten()
+        .filter_map(|mention| mention["screen_name"].as_str());
+    replied_to.chain(mentioned).any(|name| users.contains(&name.to_lowercase()))
+}
+
+/// every post of a reply chain that involves one of `users` anywhere, so both halves of a
+/// conversation stay. a chain is everything connected by replies within the archive.
+pub fn conversations_with(entries: &[Value], users: &[String]) -> HashSet<u64> {
+    let users: Vec<String> = users.iter().map(|user| user.trim_start_matches('@').to_lowercase()).collect();
+    let graph = Graph::new(entries);
+    let root = |mut id: u64| {
+        // a cycle has no root. any of its posts will do.
+        let mut seen = HashSet::from([id]);
+        while let Some(parent) = graph.posts.get(&id).and_then(|tweet| parent_id(tweet)).filter(|parent| graph.posts.contains_key(parent)) {
+            if !seen.insert(parent) {
+                break;
+            }
+            id = parent;
+        }
+        id
+    };
+    let roots: HashSet<u64> = graph.posts.iter()
+        .filter(|(_, tweet)| involves(tweet, &users))
+        .map(|(id, _)| root(*id))
+        .collect();
+    let (mut visited, mut protected) = (HashSet::new(), Vec::new());
+    for root in roots {
+        graph.bottom_up(root, &mut visited, &mut protected);
+    }
+    protected.iter().filter_map(id).collect()
+}
+
+/// reorders `posts` so that a reply is always deleted before its parent, keeping the order otherwise.
+/// intermediate states then never show a reply whose parent is already gone. posts that reply to
+/// themselves or sit in a reply cycle have no root to go with and come last.
+pub fn children_first(posts: Vec<Value>) -> Vec<Value> {
+    let graph = Graph::new(&posts);
+    let mut visited = HashSet::new();
+    let mut ordered = Vec::with_capacity(posts.len());
+    for tweet in &posts {
+        let Some(id) = id(tweet) else {
+            ordered.push(tweet.clone());
+            continue;
+        };
+        // replies are emitted together with their root. a root listed twice stays twice.
+        if parent_id(tweet).is_some_and(|parent| graph.posts.contains_key(&parent)) {
+            continue;
+        }
+        if visited.contains(&id) {
+            ordered.push(tweet.clone());
+        } else {
+            graph.bottom_up(id, &mut visited, &mut ordered);
+        }
+    }
+    let unrooted: Vec<u64> = posts.iter().filter_map(id).filter(|id| !visited.contains(id)).collect();
+    if !unrooted.is_empty() {
+        eprintln!("{} posts reply to themselves or in a cycle. deleted last. posts={}", crate::color::warning(), unrooted.len());
+    }
+    for id in unrooted {
+        graph.bottom_up(id, &mut visited, &mut ordered);
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post(id: u64, parent: Option<u64>) -> Value {
+        let mut tweet = json!({ "id": id.to_string() });
+        if let Some(parent) = parent {
+            tweet["in_reply_to_status_id_str"] = json!(parent.to_string());
+        }
+        json!({ "tweet": tweet })
+    }
+
+    fn ids(posts: &[Value]) -> Vec<u64> {
+        posts.iter().filter_map(id).collect()
+    }
+
+    #[test]
+    fn replies_go_before_their_parents() {
+        let posts = vec![post(1, None), post(2, Some(1)), post(3, Some(2)), post(4, Some(1)), post(5, None)];
+        // the newest reply's branch first.
+        assert_eq!(ids(&children_first(posts)), vec![4, 3, 2, 1, 5]);
+    }
+
+    #[test]
+    fn replies_to_posts_outside_the_set_keep_their_place() {
+        let posts = vec![post(2, Some(99)), post(1, None)];
+        assert_eq!(ids(&children_first(posts)), vec![2, 1]);
+    }
+
+    #[test]
+    fn self_replies_and_cycles_are_kept() {
+        let posts = vec![post(1, Some(1)), post(2, None), post(3, Some(4)), post(4, Some(3))];
+        let ordered = ids(&children_first(posts));
+        assert_eq!(ordered.len(), 4);
+        assert_eq!(ordered[0], 2);
+        assert!(ordered.contains(&1) && ordered.contains(&3) && ordered.contains(&4));
+    }
+
+    #[test]
+    fn entries_without_an_id_stay() {
+        let posts = vec![json!({ "tweet": {} }), post(1, None)];
+        assert_eq!(children_first(posts).len(), 2);
+    }
+
+    #[test]
+    fn conversations_end_in_cycles() {
+        let mut mention = post(3, Some(4));
+        mention["tweet"]["in_reply_to_screen_name"] = json!("Friend");
+        let posts = vec![mention, post(4, Some(3)), post(5, None)];
+        assert_eq!(conversations_with(&posts, &["@friend".to_string()]), HashSet::from([3, 4]));
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// the zone dates are shown in for people, next to UTC. archives only have UTC, which is rarely
+/// how anyone remembers when they posted something.
+#[derive(Clone, Copy, Debug)]
+pub enum Zone {
+    /// the system's.
+    Local,
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        if value.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        if let Ok(tz) = value.parse::<Tz>() {
+            return Ok(Zone::Named(tz));
+        }
+        DateTime::parse_from_str(&format!("2000-01-01 00:00 {}", value), "%Y-%m-%d %H:%M %z")
+            .map(|date| Zone::Fixed(*date.offset()))
+            .map_err(|_| format!("expect local, an IANA name such as Asia/Tokyo or an offset such as +09:00. value={}", value))
+    }
+}
+
+impl Zone {
+    pub fn format(self, instant: DateTime<Utc>, format: &str) -> String {
+        match self {
+            Zone::Local => instant.with_timezone(&Local).format(format).to_string(),
+            Zone::Named(tz) => instant.with_timezone(&tz).format(format).to_string(),
+            Zone::Fixed(offset) => instant.with_timezone(&offset).format(format).to_string(),
+        }
+    }
+
+    pub fn rfc3339(self, instant: DateTime<Utc>) -> String {
+        self.format(instant, "%Y-%m-%dT%H:%M:%S%:z")
+    }
+
+    /// `created_at=<UTC> local=<this zone>` for key=value lines. a date that doesn't parse is shown as it is.
+    pub fn fields(self, created_at: &str) -> String {
+        match parse(created_at) {
+            Some(instant) => format!("created_at={} local={}", instant.format("%Y-%m-%dT%H:%M:%SZ"), self.rfc3339(instant)),
+            None => format!("created_at={}", created_at),
+        }
+    }
+}
+
+/// `created_at` of an archive entry.
+pub fn parse(created_at: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(created_at, "%a %b %d %H:%M:%S %z %Y").ok().map(|created_at| created_at.to_utc())
+}
@@ -0,0 +1,22 @@
+use chrono::DateTime;
+use std::ffi::OsString;
+
+/// `--version --verbose`, read off the raw arguments since clap answers --version on its own.
+pub fn verbose_requested(args: &[OsString]) -> bool {
+    let args: Vec<_> = args.iter().skip(1).take_while(|arg| *arg != "--").collect();
+    args.iter().any(|arg| *arg == "--version" || *arg == "-V") && args.iter().any(|arg| *arg == "--verbose")
+}
+
+/// the version plus where the build came from, for bug reports.
+pub fn print_verbose() {
+    let built = env!("POST_REMOVE_BUILT").parse().ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .map_or_else(|| "unknown".to_string(), |built| built.to_rfc3339());
+    let features = env!("POST_REMOVE_FEATURES");
+    println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    println!("commit: {}", env!("POST_REMOVE_COMMIT"));
+    println!("built: {}", built);
+    println!("target: {}", env!("POST_REMOVE_TARGET"));
+    println!("profile: {}", env!("POST_REMOVE_PROFILE"));
+    println!("features: {}", if features.is_empty() { "none" } else { features });
+}
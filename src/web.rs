@@ -0,0 +1,290 @@
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Html, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashSet, net::SocketAddr, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
+
+use crate::{status::SharedStatus, text, thread, timezone::{self, Zone}};
+
+/// what the page changes while the run goes on. the run starts paused, so Start is the confirmation.
+pub struct Controls {
+    paused: AtomicBool,
+    kept: Mutex<HashSet<u64>>,
+}
+
+impl Controls {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn is_kept(&self, id: u64) -> bool {
+        self.kept.lock().expect("kept lock poisoned.").contains(&id)
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct Candidate {
+    id: String,
+    /// UTC, and in --timezone.
+    created_at: String,
+    local: String,
+    text: String,
+}
+
+/// header the page sends the startup token in.
+const TOKEN_HEADER: &str = "x-post-remove-token";
+
+/// what a request has to show. the token is only printed to the terminal, and the Host check
+/// keeps a page on another site from reaching us through DNS rebinding.
+struct Guard {
+    token: String,
+    hosts: [String; 2],
+}
+
+impl Guard {
+    fn host_allowed(&self, headers: &HeaderMap) -> bool {
+        headers.get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .is_some_and(|host| self.hosts.iter().any(|allowed| allowed == host))
+    }
+
+    fn token_matches(&self, headers: &HeaderMap) -> bool {
+        headers.get(TOKEN_HEADER).is_some_and(|token| token.as_bytes() == self.token.as_bytes())
+    }
+}
+
+async fn guard(State(guard): State<Arc<Guard>>, request: Request, next: Next) -> Result<Response, StatusCode> {
+    if !guard.host_allowed(request.headers()) {
+        return Err(StatusCode::MISDIRECTED_REQUEST);
+    }
+    if request.method() != axum::http::Method::GET && !guard.token_matches(request.headers()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(request).await)
+}
+
+fn token() -> String {
+    (0..16).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+}
+
+struct Shared {
+    controls: Arc<Controls>,
+    candidates: Vec<Candidate>,
+    status: SharedStatus,
+}
+
+#[derive(Serialize)]
+struct Post {
+    #[serde(flatten)]
+    candidate: Candidate,
+    kept: bool,
+}
+
+#[derive(Serialize)]
+struct RunStatus {
+    paused: bool,
+    current: Option<String>,
+    processed: usize,
+    remaining: usize,
+    failed: usize,
+    waiting: Option<String>,
+}
+
+async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+async fn list(State(shared): State<Arc<Shared>>) -> Json<Vec<Post>> {
+    let kept = shared.controls.kept.lock().expect("kept lock poisoned.");
+    Json(shared.candidates.iter()
+        .map(|candidate| Post { kept: candidate.id.parse().is_ok_and(|id| kept.contains(&id)), candidate: candidate.clone() })
+        .collect())
+}
+
+async fn toggle(State(shared): State<Arc<Shared>>, Path(id): Path<u64>) -> Result<Json<bool>, StatusCode> {
+    if !shared.candidates.iter().any(|candidate| candidate.id == id.to_string()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut kept = shared.controls.kept.lock().expect("kept lock poisoned.");
+    let keep = !kept.remove(&id);
+    if keep {
+        kept.insert(id);
+    }
+    println!("web ui. id={} keep={}", id, keep);
+    Ok(Json(keep))
+}
+
+fn set_paused(shared: &Shared, paused: bool) -> Json<RunStatus> {
+    shared.controls.paused.store(paused, Ordering::SeqCst);
+    println!("web ui. {}.", if paused { "paused" } else { "started" });
+    status(shared)
+}
+
+async fn start(State(shared): State<Arc<Shared>>) -> Json<RunStatus> {
+    set_paused(&shared, false)
+}
+
+async fn pause(State(shared): State<Arc<Shared>>) -> Json<RunStatus> {
+    set_paused(&shared, true)
+}
+
+async fn current(State(shared): State<Arc<Shared>>) -> Json<RunStatus> {
+    status(&shared)
+}
+
+fn status(shared: &Shared) -> Json<RunStatus> {
+    let status = shared.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Json(RunStatus {
+        paused: shared.controls.is_paused(),
+        current: status.current.map(|id| id.to_string()),
+        processed: status.processed,
+        remaining: status.remaining,
+        failed: status.failed,
+        waiting: status.waiting.as_ref().map(|(reason, _)| reason.clone()),
+    })
+}
+
+fn router(shared: Arc<Shared>, guard_state: Arc<Guard>) -> Router {
+    Router::new()
+        .route("/", get(page))
+        .route("/api/posts", get(list))
+        .route("/api/posts/:id/toggle", post(toggle))
+        .route("/api/status", get(current))
+        .route("/api/start", post(start))
+        .route("/api/pause", post(pause))
+        .with_state(shared)
+        .layer(middleware::from_fn_with_state(guard_state, guard))
+}
+
+/// serves the page on `addr` in the background. only loopback addresses are accepted, requests
+/// must name that address in Host, and anything that changes the run needs the token printed at startup.
+pub async fn serve(addr: SocketAddr, posts: &[Value], status: SharedStatus, zone: Zone) -> Result<Arc<Controls>> {
+    anyhow::ensure!(addr.ip().is_loopback(), "--web-ui only listens on loopback addresses. addr={}", addr);
+    let controls = Arc::new(Controls { paused: AtomicBool::new(true), kept: Mutex::new(HashSet::new()) });
+    let candidates = posts.iter()
+        .filter(|tweet| thread::id(tweet).is_some())
+        .map(|tweet| {
+            let created_at = tweet["tweet"]["created_at"].as_str().unwrap_or_default();
+            let parsed = timezone::parse(created_at);
+            Candidate {
+                id: tweet["tweet"]["id"].as_str().unwrap_or_default().to_string(),
+                created_at: parsed.map_or_else(|| created_at.to_string(), |parsed| parsed.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+                local: parsed.map(|parsed| zone.format(parsed, "%Y-%m-%d %H:%M:%S %:z")).unwrap_or_default(),
+                text: text::display(&tweet["tweet"]),
+            }
+        })
+        .collect();
+    let shared = Arc::new(Shared { controls: controls.clone(), candidates, status });
+    let listener = tokio::net::TcpListener::bind(addr).await.with_context(|| format!("failed to listen. addr={}", addr))?;
+    let local = listener.local_addr()?;
+    let guard_state = Arc::new(Guard { token: token(), hosts: [local.to_string(), format!("localhost:{}", local.port())] });
+    let url = format!("http://{}/?token={}", local, guard_state.token);
+    let router = router(shared, guard_state);
+    println!("web ui. open {} and press start.", url);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, router).await {
+            eprintln!("web ui stopped. err={}", err);
+        }
+    });
+    Ok(controls)
+}
+
+const PAGE: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>post_remove</title>
+<style>
+body { font-family: sans-serif; margin: 1em; }
+table { border-collapse: collapse; width: 100%; }
+td { border-bottom: 1px solid #ddd; padding: 4px; }
+tr.kept td { color: #999; text-decoration: line-through; }
+#status { margin: 1em 0; }
+</style>
+</head>
+<body>
+<button onclick="send('/api/start')">start</button>
+<button onclick="send('/api/pause')">pause</button>
+<div id="status"></div>
+<table id="posts"></table>
+<script>
+const token = new URLSearchParams(location.search).get('token') || '';
+function post(url) {
+  return fetch(url, { method: 'POST', headers: { 'X-Post-Remove-Token': token } });
+}
+async function send(url) {
+  render(await (await post(url)).json());
+}
+function render(status) {
+  document.getElementById('status').textContent =
+    (status.paused ? 'paused' : 'running') + '. processed=' + status.processed + ' remaining=' + status.remaining +
+    ' failed=' + status.failed + ' current=' + (status.current || '-') + (status.waiting ? ' waiting for ' + status.waiting : '');
+}
+async function toggle(id, row) {
+  const keep = await (await post('/api/posts/' + id + '/toggle')).json();
+  row.className = keep ? 'kept' : '';
+  row.querySelector('input').checked = keep;
+}
+async function load() {
+  const table = document.getElementById('posts');
+  for (const post of await (await fetch('/api/posts')).json()) {
+    const row = table.insertRow();
+    row.className = post.kept ? 'kept' : '';
+    const box = document.createElement('input');
+    box.type = 'checkbox';
+    box.checked = post.kept;
+    box.title = 'keep';
+    box.onclick = (event) => { event.preventDefault(); toggle(post.id, row); };
+    row.insertCell().appendChild(box);
+    row.insertCell().textContent = post.id;
+    row.insertCell().textContent = post.local ? `${post.local} (${post.created_at})` : post.created_at;
+    row.insertCell().textContent = post.text;
+  }
+}
+load();
+setInterval(async () => render(await (await fetch('/api/status')).json()), 2000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn needs_a_local_host_and_the_token() {
+        let controls = Arc::new(Controls { paused: AtomicBool::new(true), kept: Mutex::new(HashSet::new()) });
+        let shared = Arc::new(Shared { controls: controls.clone(), candidates: Vec::new(), status: SharedStatus::default() });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local = listener.local_addr().unwrap();
+        let guard_state = Arc::new(Guard { token: "secret".to_string(), hosts: [local.to_string(), format!("localhost:{}", local.port())] });
+        let router = router(shared, guard_state);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::new();
+        let send = |path: &str, method: reqwest::Method, host: String, token: Option<&str>| {
+            let mut request = client.request(method, format!("http://{}{}", local, path)).header(header::HOST.as_str(), host);
+            if let Some(token) = token {
+                request = request.header(TOKEN_HEADER, token);
+            }
+            async move { request.send().await.unwrap().status().as_u16() }
+        };
+        assert_eq!(send("/api/status", reqwest::Method::GET, local.to_string(), None).await, 200);
+        assert_eq!(send("/api/status", reqwest::Method::GET, format!("localhost:{}", local.port()), None).await, 200);
+        // a rebound name resolves to us but keeps its own Host.
+        assert_eq!(send("/api/status", reqwest::Method::GET, format!("evil.example:{}", local.port()), None).await, 421);
+        assert_eq!(send("/api/start", reqwest::Method::POST, local.to_string(), None).await, 403);
+        assert_eq!(send("/api/start", reqwest::Method::POST, local.to_string(), Some("guess")).await, 403);
+        assert!(controls.is_paused());
+        assert_eq!(send("/api/start", reqwest::Method::POST, local.to_string(), Some("secret")).await, 200);
+        assert!(!controls.is_paused());
+    }
+}